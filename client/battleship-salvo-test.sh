@@ -1,8 +1,19 @@
 #/bin/sh
 
+# Placements are sent unencrypted, so start the server with --allow-plaintext.
+# Any broker will do, including the one started by the server with --embedded-broker <port>.
+# Shots are signed with the sign tool of the server. Both players share a secret, so every shot is signed correctly
+# whoever's turn it is.
+
+SECRET="cockadoodledoo"
+MANIFEST="$(dirname "$0")/../server/Cargo.toml"
+SIGN="cargo run --quiet --manifest-path ${MANIFEST} --bin sign -- ${SECRET}"
+# Build the sign tool before the game starts, so building does not eat into the first turn.
+cargo build --quiet --manifest-path "${MANIFEST}" --bin sign
+
 # Enroll players
-mosquitto_pub -u "${4}" -h "${1}" -t /${4}/game/request -m "{ \"name\": \"${2}\", \"secret\": \"cockadoodledoo\"}"
-mosquitto_pub -u "${4}" -h "${1}" -t /${4}/game/request -m "{ \"name\": \"${3}\", \"secret\": \"cockadoodlegloo\"}"
+mosquitto_pub -u "${4}" -h "${1}" -t /${4}/game/request -m "{ \"name\": \"${2}\", \"secret\": \"${SECRET}\"}"
+mosquitto_pub -u "${4}" -h "${1}" -t /${4}/game/request -m "{ \"name\": \"${3}\", \"secret\": \"${SECRET}\"}"
 
 # Place ships
 mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${2}/ships/battleship/place -m '{ "coordinates": { "x": 9, "y": 5 }, "orientation": "Vertical" }'
@@ -22,28 +33,28 @@ mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${3}/ships/patrolboat/place -
 sleep 0.1
 
 ## Destroy battleship
-mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${3}/fire -m '{ "x": 9, "y": 5 }'
-mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${3}/fire -m '{ "x": 9, "y": 6 }'
-mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${3}/fire -m '{ "x": 9, "y": 7 }'
-mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${3}/fire -m '{ "x": 9, "y": 8 }'
+mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${3}/fire -m "$(${SIGN} '{ "x": 9, "y": 5 }')"
+mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${3}/fire -m "$(${SIGN} '{ "x": 9, "y": 6 }')"
+mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${3}/fire -m "$(${SIGN} '{ "x": 9, "y": 7 }')"
+mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${3}/fire -m "$(${SIGN} '{ "x": 9, "y": 8 }')"
 
 ## Destroy carrier
-mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${3}/fire -m '{ "x": 7, "y": 5 }'
-mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${3}/fire -m '{ "x": 7, "y": 6 }'
-mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${3}/fire -m '{ "x": 7, "y": 7 }'
-mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${3}/fire -m '{ "x": 7, "y": 8 }'
-mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${3}/fire -m '{ "x": 7, "y": 9 }'
+mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${3}/fire -m "$(${SIGN} '{ "x": 7, "y": 5 }')"
+mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${3}/fire -m "$(${SIGN} '{ "x": 7, "y": 6 }')"
+mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${3}/fire -m "$(${SIGN} '{ "x": 7, "y": 7 }')"
+mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${3}/fire -m "$(${SIGN} '{ "x": 7, "y": 8 }')"
+mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${3}/fire -m "$(${SIGN} '{ "x": 7, "y": 9 }')"
 
 ## Destroy destroyer
-mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${3}/fire -m '{ "x": 5, "y": 5 }'
-mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${3}/fire -m '{ "x": 5, "y": 6 }'
-mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${3}/fire -m '{ "x": 5, "y": 7 }'
+mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${3}/fire -m "$(${SIGN} '{ "x": 5, "y": 5 }')"
+mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${3}/fire -m "$(${SIGN} '{ "x": 5, "y": 6 }')"
+mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${3}/fire -m "$(${SIGN} '{ "x": 5, "y": 7 }')"
 
 ## Destroy submarine
-mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${3}/fire -m '{ "x": 3, "y": 5 }'
-mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${3}/fire -m '{ "x": 3, "y": 6 }'
-mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${3}/fire -m '{ "x": 3, "y": 7 }'
+mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${3}/fire -m "$(${SIGN} '{ "x": 3, "y": 5 }')"
+mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${3}/fire -m "$(${SIGN} '{ "x": 3, "y": 6 }')"
+mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${3}/fire -m "$(${SIGN} '{ "x": 3, "y": 7 }')"
 
 ## Destroy patrolboat
-mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${3}/fire -m '{ "x": 1, "y": 5 }'
-mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${3}/fire -m '{ "x": 1, "y": 6 }'
+mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${3}/fire -m "$(${SIGN} '{ "x": 1, "y": 5 }')"
+mosquitto_pub -u "${4}" -h "${1}" -t /${4}/players/${3}/fire -m "$(${SIGN} '{ "x": 1, "y": 6 }')"
@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+use crate::point::Point;
+
+#[derive(Serialize, Deserialize, Debug, PartialOrd, PartialEq, Clone)]
+pub struct Class {
+    pub name: String,
+    pub length: u8,
+    pub token: String,
+    #[serde(default)]
+    pub shape: Vec<Point>,
+}
@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, PartialOrd, PartialEq, Clone)]
+pub struct Current {
+    pub player: String,
+    pub deadline: u64,
+    #[serde(default)]
+    pub shots: u8,
+}
@@ -2,6 +2,7 @@ use std::io;
 use std::io::BufRead;
 use crate::orientation::Orientation;
 use crate::orientation::Orientation::{Horizontal, Vertical};
+use crate::size::Size;
 
 pub fn grab_string() -> String {
     io::stdin().lock().lines().next().unwrap().unwrap()
@@ -15,11 +16,11 @@ pub fn grab_number() -> u8 {
     }
 }
 
-pub fn grab_coordinate(axis: &str) -> u8 {
+pub fn grab_coordinate(axis: &str, length: u8) -> u8 {
     loop {
         println!("{} = ", axis);
         let distance = grab_number();
-        if distance <= 9 {
+        if distance < length {
             return distance;
         } else {
             println!("{} is out of bounds!", distance);
@@ -27,8 +28,8 @@ pub fn grab_coordinate(axis: &str) -> u8 {
     }
 }
 
-pub fn grab_coordinates() -> (u8, u8) {
-    (grab_coordinate("x"), grab_coordinate("y"))
+pub fn grab_coordinates(size: &Size) -> (u8, u8) {
+    (grab_coordinate("x", size.width), grab_coordinate("y", size.height))
 }
 
 pub(crate) fn grab_orientation() -> Orientation {
@@ -43,3 +44,22 @@ pub(crate) fn grab_orientation() -> Orientation {
         }
     }
 }
+
+pub fn grab_rotation() -> u8 {
+    loop {
+        match grab_number() {
+            rotation if rotation < 4 => return rotation,
+            other => eprintln!("{} not a valid rotation", other),
+        }
+    }
+}
+
+pub fn grab_mirrored() -> bool {
+    loop {
+        match grab_number() {
+            0 => return false,
+            1 => return true,
+            other => eprintln!("{} not a valid mirror specifier", other),
+        }
+    }
+}
@@ -2,10 +2,13 @@ use crate::mqtt::{Mosquitto, MosquittoArc};
 use log::{info, error};
 
 mod attack;
+mod class;
+mod current;
 mod grab;
 mod orientation;
 mod mqtt;
 mod point;
+mod size;
 mod translate;
 mod turn;
 
@@ -18,15 +21,23 @@ use std::io::{Read, BufRead};
 use crate::orientation::Orientation::{Horizontal, Vertical};
 use std::fmt::{Display, Formatter, format};
 use core::fmt;
-use crate::grab::{grab_number, grab_string, grab_coordinates, grab_orientation};
+use crate::grab::{grab_number, grab_string, grab_coordinates, grab_orientation, grab_rotation, grab_mirrored};
 use translate::{
     deserialize,
+    encrypt,
+    sign,
     TranslationError
 };
 use std::sync::mpsc::{Sender, Receiver};
 use std::sync::{mpsc, Mutex, Arc};
 
 use crate::turn::Turn;
+use crate::current::Current;
+use crate::class::Class;
+use crate::size::Size;
+use crate::point::Point;
+
+const SECRET: &str = "Cockadoodledoo";
 
 enum State {
     Lobby,
@@ -64,8 +75,9 @@ impl Iterator for Game {
         }
 
         loop {
-            let current_player = handle.block_on(mqtt.clone().await_topic("/game/current")).1;
-            if current_player == self.name {
+            let current = handle.block_on(mqtt.clone().await_topic("/game/current")).1;
+            let current: Result<Current, TranslationError> = deserialize(&current);
+            if current.map_or(false, |current| current.player == self.name) {
                 let mqtt = &mut mqtt.clone();
                 return Some(Turn::new(
                     self.players.iter()
@@ -94,6 +106,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // let secret = grab_string();
     println!("Enter your team channel: ");
     let prefix = grab_string();
+    println!("Enter the team you play for (leave empty to play on your own): ");
+    let team = grab_string();
     println!("{} playing on channel {}", player, prefix);
 
     let mut mqtt = MosquittoArc::new("nvs0495", 1883, &prefix, &player);
@@ -107,7 +121,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     while !player_list.contains(&player) {
         player_list = mqtt.await_response(
             format!("/{}/game/request", &prefix),
-            format!("{{ \"name\": \"{}\", \"secret\": \"Cockadoodledoo\"}}", &player),
+            if team.is_empty() {
+                format!("{{ \"name\": \"{}\", \"secret\": \"{}\"}}", &player, SECRET)
+            } else {
+                format!("{{ \"name\": \"{}\", \"secret\": \"{}\", \"team\": \"{}\"}}", &player, SECRET, &team)
+            },
             format!("/{}/players/list", &prefix)
         ).await.1;
         sleep(Duration::from_millis(1)).await;
@@ -118,20 +136,41 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         |topic, payload| println!("Number of players: {}", payload)
     );
 
+    let fleet = mqtt.await_topic(format!("/{}/game/fleet", &prefix)).await.1;
+    let fleet: Vec<Class> = deserialize(&fleet).unwrap_or_default();
+    let size = mqtt.await_topic(format!("/{}/game/size", &prefix)).await.1;
+    let size: Size = deserialize(&size).unwrap_or_default();
+
     sleep(Duration::from_millis(1)).await;
-    for (_, ship) in [ "carrier", "battleship", "destroyer", "submarine", "patrolboat" ].iter().enumerate() {
+    for class in fleet.iter() {
+        let ship = &class.name;
         let mut success = false;
         while !success {
-            println!("Enter coordinates [0 - 9] for {}:", ship);
-            let (x, y) = grab_coordinates();
+            println!("Enter coordinates [0 - {}; 0 - {}] for {}:", size.width - 1, size.height - 1, ship);
+            let (x, y) = grab_coordinates(&size);
 
             println!("Enter orientation [0 = Horizontal, 1 = Vertical]: ");
             let orientation = grab_orientation();
 
+            let (rotation, mirrored) = if class.shape.is_empty() {
+                (0, false)
+            } else {
+                println!("Enter rotation [0 - 3 clockwise quarter turns]: ");
+                let rotation = grab_rotation();
+                println!("Mirror the ship [0 = No, 1 = Yes]: ");
+                (rotation, grab_mirrored())
+            };
+
             println!("Requesting placement at [{}; {}], oriented {}.", x, y, orientation);
             mqtt.publish(
                 format!("/{}/players/{}/ships/{}/place", prefix, &player, ship),
-                format!("{{ \"coordinates\": {{ \"x\": {}, \"y\": {} }}, \"orientation\": \"{}\" }}", x, y, orientation)
+                encrypt(
+                    format!(
+                        "{{ \"coordinates\": {{ \"x\": {}, \"y\": {} }}, \"orientation\": \"{}\", \"rotation\": {}, \"mirrored\": {} }}",
+                        x, y, orientation, rotation, mirrored
+                    ),
+                    SECRET
+                )
             );
 
             mqtt.await_topic(format!("/{}/players/{}/ships/{}/place", prefix, &player, ship)).await;
@@ -174,7 +213,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     );
     let players = receiver.recv().unwrap();
 
-    let mut boats = 5;
+    let current: Result<Current, TranslationError> = deserialize(
+        &mqtt.clone().await_topic(format!("/{}/game/current", &prefix)).await.1
+    );
+    let boats = current.map_or(fleet.len() as u8, |current| current.shots);
     let target = loop {
         println!("Choose player to attack [{:?}]:", players.iter().enumerate());
         let player_index = grab_number();
@@ -185,10 +227,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     loop {
         println!("Put in some coordinates to fire at:");
-        let (x, y) = grab_coordinates();
+        let (x, y) = grab_coordinates(&size);
         let shots_fired = mqtt.await_response(
             format!("/{}/players/{}/fire", &prefix, &target),
-            format!("{{ \"x\": \"{}\", \"y\": \"{}\"}}", &x, &y),
+            sign(Point::new(x, y), SECRET).expect("Unable to sign the shot"),
             format!("/{}/game/fired_shots", &prefix),
         ).await.1;
         let shots_fired: u8 = shots_fired.parse().unwrap();
@@ -1,13 +1,16 @@
-use crate::mqtt::Request::{Subscription, Publication};
+use crate::mqtt::Request::{Subscription, Publication, Connected};
 
-use std::collections::HashMap;
+use std::cmp::min;
+use std::collections::{HashMap, VecDeque};
 use std::future::Future;
+use std::time::Duration;
 use std::sync::{
     Arc, Mutex, mpsc::{self, Sender, Receiver}
 };
 
-use rumqttc::{self, AsyncClient, Event, MqttOptions, Packet, QoS};
+use rumqttc::{self, AsyncClient, Event, LastWill, MqttOptions, Packet, QoS};
 use tokio::task;
+use tokio::time::sleep;
 use tokio::task::JoinHandle;
 use futures::future::{join3, Join3};
 use delegate::delegate;
@@ -22,8 +25,15 @@ pub enum Request {
     Subscription(String, Box<dyn Fn(&String, &String) + Send + 'static>),
     Unsubscription(String),
     Publication(String, String, bool),
+    /// The connection to the broker was established, or re-established after it was lost.
+    Connected,
 }
 
+const MIN_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// Publications held back while disconnected, beyond this the oldest are dropped.
+const MAX_BUFFERED: usize = 1000;
+
 pub struct Mosquitto {
     running: Arc<Mutex<bool>>,
     sender: Sender<Request>,
@@ -82,14 +92,31 @@ impl MosquittoArc {
     }
 }
 
+/// Subscribes to the topic, retrying with backoff as long as the client refuses the request.
+async fn subscribe_with_retry(client: &AsyncClient, topic: &str, running: &Mutex<bool>) {
+    let mut backoff = MIN_BACKOFF;
+    while let Err(error) = client.subscribe(topic, QoS::ExactlyOnce).await {
+        if !*running.lock().unwrap() {
+            return;
+        }
+        error!("Unable to subscribe to topic: {}, retrying in {:?}: {:?}", topic, backoff, error);
+        sleep(backoff).await;
+        backoff = min(backoff * 2, MAX_BACKOFF);
+    }
+}
+
 impl Mosquitto {
     pub fn new(
         host: impl Into<String>, port: u16, tokio_handle: Handle, user: impl Into<String>, id: impl Into<String>
     ) -> (Mosquitto, Join3<JoinHandle<()>, impl Future<Output=()>, JoinHandle<()>>) {
+        let id = id.into();
         let user = user.into();
-        let mut mqttoptions = MqttOptions::new(id.into(), host.into(), port);
+        // Retained "online" while connected, the broker publishes "offline" when the connection is lost.
+        let connection_topic = format!("/connections/{}", &id);
+        let mut mqttoptions = MqttOptions::new(id, host.into(), port);
         mqttoptions.set_credentials(user, String::from(""));
         mqttoptions.set_keep_alive(5);
+        mqttoptions.set_last_will(LastWill::new(&connection_topic, "offline", QoS::AtLeastOnce, true));
 
         let (client, mut eventloop) = AsyncClient::new(mqttoptions, 10);
         let callbacks = Arc::new(Mutex::new(HashMap::<String, (Regex, Vec<Box<dyn Fn(&String, &String) + Send + 'static>>)>::new()));
@@ -102,8 +129,13 @@ impl Mosquitto {
         let running_for_eventloop = Arc::clone(&running);
         let running_for_poller1 = Arc::clone(&running);
         let running_for_poller2 = Arc::clone(&running);
+        let running_for_subscriptions = Arc::clone(&running);
+        let connected = Arc::new(Mutex::from(false));
+        let connected_for_eventloop = Arc::clone(&connected);
+        let connected_for_poller = Arc::clone(&connected);
 
         let (subscription_sender, subscription_receiver): (Sender<Request>, Receiver<Request>) = mpsc::channel();
+        let connection_sender = subscription_sender.clone();
 
         (
             Mosquitto {
@@ -112,22 +144,43 @@ impl Mosquitto {
             },
             join3(
                 tokio_handle.spawn(async move {
+                    let mut backoff = MIN_BACKOFF;
                     while *running_for_eventloop.lock().unwrap() {
-                        if let Ok(event) = eventloop.poll().await {
-                            if let Event::Incoming(incoming) = event {
-                                if let Packet::Publish(publish) = incoming {
-                                    if let Ok(payload) = String::from_utf8(publish.payload.to_vec()) {
-                                        event_sender.send((publish.topic.clone(), payload.clone())).unwrap()
+                        match eventloop.poll().await {
+                            Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                                info!("Connected to the broker");
+                                backoff = MIN_BACKOFF;
+                                connection_sender.send(Connected).ok();
+                            }
+                            Ok(Event::Incoming(Packet::Publish(publish))) => {
+                                if let Ok(payload) = String::from_utf8(publish.payload.to_vec()) {
+                                    event_sender.send((publish.topic.clone(), payload.clone())).unwrap()
+                                }
+                            }
+                            Ok(_) => {}
+                            Err(error) => {
+                                {
+                                    let mut connected = connected_for_eventloop.lock().unwrap();
+                                    if *connected {
+                                        error!("Lost the connection to the broker: {:?}", error);
+                                    } else {
+                                        error!("Unable to connect to the broker, retrying in {:?}: {:?}", backoff, error);
                                     }
+                                    *connected = false;
                                 }
+                                sleep(backoff).await;
+                                backoff = min(backoff * 2, MAX_BACKOFF);
                             }
                         }
                     }
                 }),
                 async move {
+                    // Publications made while disconnected, sent once the connection is back.
+                    let mut buffered = VecDeque::new();
                     while *running_for_poller1.lock().unwrap() {
                         match subscription_receiver.recv() {
                             Ok(request) => {
+                                let connected = *connected_for_poller.lock().unwrap();
                                 match request {
                                     Subscription(topic, callback) => {
                                         info!("Subscribing to topic: {}", &topic);
@@ -147,15 +200,42 @@ impl Mosquitto {
                                                 );
                                             },
                                         }
-                                        client.subscribe(&topic, QoS::ExactlyOnce).await.unwrap();
+                                        drop(callbacks);
+                                        if connected {
+                                            subscribe_with_retry(&client, &topic, &running_for_subscriptions).await;
+                                        }
                                     }
-                                    Publication(topic, payload, retain) => {
+                                    Publication(topic, payload, retain) if connected => {
                                         info!("Publishing to topic: {}, the following: {}", topic, payload);
                                         client.publish(topic, QoS::ExactlyOnce, retain, payload).await.unwrap();
                                     }
+                                    Publication(topic, payload, retain) => {
+                                        info!("Not connected, holding back publication to topic: {}", topic);
+                                        if buffered.len() == MAX_BUFFERED {
+                                            if let Some((dropped, _, _)) = buffered.pop_front() {
+                                                error!("Holding back too many publications, dropping: {}", dropped);
+                                            }
+                                        }
+                                        buffered.push_back((topic, payload, retain));
+                                    }
                                     Unsubscription(topic) => {
                                         callbacks_for_poller.lock().unwrap().remove(&topic);
-                                        client.unsubscribe(topic).await.unwrap();
+                                        if connected {
+                                            client.unsubscribe(topic).await.unwrap();
+                                        }
+                                    }
+                                    Connected => {
+                                        *connected_for_poller.lock().unwrap() = true;
+                                        client.publish(&connection_topic, QoS::AtLeastOnce, true, "online").await.unwrap();
+                                        let topics: Vec<String> = callbacks_for_poller.lock().unwrap().keys().cloned().collect();
+                                        for topic in topics {
+                                            info!("Subscribing to topic: {}", &topic);
+                                            subscribe_with_retry(&client, &topic, &running_for_subscriptions).await;
+                                        }
+                                        for (topic, payload, retain) in buffered.drain(..) {
+                                            info!("Publishing to topic: {}, the following: {}", topic, payload);
+                                            client.publish(topic, QoS::ExactlyOnce, retain, payload).await.unwrap();
+                                        }
                                     }
                                 }
                             }
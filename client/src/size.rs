@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, PartialOrd, PartialEq, Clone, Copy)]
+pub struct Size {
+    pub(crate) width: u8,
+    pub(crate) height: u8,
+}
+
+impl Default for Size {
+    fn default() -> Self {
+        Size {
+            width: 10, height: 10,
+        }
+    }
+}
@@ -0,0 +1,4 @@
+fn main() {
+    println!("cargo:rerun-if-changed=proto/battleship.proto");
+    prost_build::compile_protos(&["proto/battleship.proto"], &["proto/"]).expect("Unable to compile the protobuf schema");
+}
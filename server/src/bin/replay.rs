@@ -0,0 +1,62 @@
+use battleship_salvo_server::model::{
+    game::Game,
+    event::{Entry, Event},
+    size::Size,
+    config::Config,
+};
+use battleship_salvo_server::messaging::translate::{self, deserialize};
+use std::env;
+use std::fs;
+use std::path::Path;
+
+fn print_boards(game: &Game) {
+    println!("{}", game.state);
+    for player in &game.players {
+        println!("{}{}", player.name, player);
+    }
+}
+
+fn print_shots(game: &Game) {
+    for player in &game.players {
+        for (target, shots) in player.shots() {
+            let shots: Vec<String> = shots.iter().map(|shot| shot.to_string()).collect();
+            println!("{} fired at {}: {}", player.name, target, shots.join(" "));
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 2 {
+        eprintln!("Please pass the journal file to replay as a command line argument!");
+        return;
+    }
+
+    let journal = match fs::read_to_string(&args[1]) {
+        Ok(journal) => journal,
+        Err(error) => {
+            eprintln!("Unable to read journal {}: {:?}", &args[1], error);
+            return;
+        }
+    };
+    let prefix = Path::new(&args[1]).file_stem().map_or(String::new(), |stem| stem.to_string_lossy().into());
+    let mut game = Game::new(Size::default(), prefix, Config::default());
+
+    for (number, line) in journal.lines().enumerate().filter(|(_, line)| !line.is_empty()) {
+        let line = line.to_string();
+        let entry: translate::Result<Entry> = deserialize(&line);
+        match entry {
+            Ok(entry) => {
+                println!("[{}] {:?}", entry.timestamp, entry.event);
+                entry.event.apply(&mut game);
+                match entry.event {
+                    Event::TurnChanged { .. } | Event::GameOver { .. } => print_boards(&game),
+                    _ => {}
+                }
+            }
+            Err(error) => eprintln!("Skipping line {} of the journal: {:?}", number + 1, error),
+        }
+    }
+    print_boards(&game);
+    print_shots(&game);
+}
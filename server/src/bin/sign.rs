@@ -0,0 +1,17 @@
+use battleship_salvo_server::model::point::Point;
+use battleship_salvo_server::messaging::translate::{self, deserialize, sign};
+use std::env;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 3 {
+        eprintln!("Please pass the secret and the coordinates to sign as command line arguments!");
+        return;
+    }
+
+    let coordinates: translate::Result<Point> = deserialize(&args[2]);
+    match coordinates.and_then(|coordinates| sign(coordinates, &args[1])) {
+        Ok(signed) => println!("{}", signed),
+        Err(error) => eprintln!("Unable to sign {}: {:?}", &args[2], error),
+    }
+}
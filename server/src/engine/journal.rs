@@ -0,0 +1,52 @@
+use crate::model::event::Entry;
+use crate::messaging::translate::{serialize, TranslationError};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::fmt::{self, Display, Formatter};
+use std::sync::Mutex;
+use lazy_static::lazy_static;
+
+lazy_static! {
+    static ref WRITER: Mutex<()> = Mutex::new(());
+}
+
+#[derive(Debug)]
+pub enum JournalError {
+    IoError(io::Error),
+    TranslationError(TranslationError),
+}
+
+impl Display for JournalError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            JournalError::IoError(error) => write!(f, "{}", error),
+            JournalError::TranslationError(error) => write!(f, "{:?}", error),
+        }
+    }
+}
+
+impl From<io::Error> for JournalError {
+    fn from(error: io::Error) -> Self {
+        JournalError::IoError(error)
+    }
+}
+
+impl From<TranslationError> for JournalError {
+    fn from(error: TranslationError) -> Self {
+        JournalError::TranslationError(error)
+    }
+}
+
+pub fn append(directory: &str, prefix: &str, entry: &Entry) -> Result<(), JournalError> {
+    let line = serialize(entry)? + "\n";
+    // Timers and message callbacks record from different threads, keep their lines in order.
+    let _writer = WRITER.lock().unwrap();
+    fs::create_dir_all(directory)?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(Path::new(directory).join(format!("{}.jsonl", prefix)))?;
+    file.write_all(line.as_bytes())?;
+    Ok(())
+}
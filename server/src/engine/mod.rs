@@ -1,8 +1,12 @@
 
+mod journal;
+mod registry;
+mod snapshot;
+mod tests;
+
 use crate::model::{
-    game::GameArc,
+    game::{Game, GameArc},
     player::Player,
-    class::Class,
     ship::Ship,
     gameerror::GameError,
     gamestate::GameState::{
@@ -10,59 +14,337 @@ use crate::model::{
     },
     point::Point,
     impact::Impact,
-    size::Size,
+    config::{Config, StragglerPolicy},
+    turn::Turn,
+    event::{Entry, Event},
+    rules::Rules,
+    class::Class,
+    disclosure::Disclosure,
+    shotresult::ShotResult,
+    friendlyfire::FriendlyFire,
+    targetpolicy::TargetPolicy,
+    salvo::{SalvoShot, SalvoResult},
 };
 use crate::messaging::{
     translate,
-    translate::deserialize,
     translate::serialize,
+    translate::serialize_with,
+    translate::deserialize_with,
     translate::decrypt,
     mqtt::MosquittoArc,
+    transport::Transport,
+    broker::Broker,
+    websocket::Gateway,
 };
-use log::info;
-use strum::IntoEnumIterator;
+use log::{info, error};
+use registry::GameRegistry;
 use rand::Rng;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::time::sleep;
 
-pub async fn start_engine(size: Size, prefix: impl Into<String>, host: impl Into<String>, port: u16, user: impl Into<String>) {
+pub async fn start_engine(
+    config: Config, prefix: impl Into<String>, host: impl Into<String>, port: u16, user: impl Into<String>
+) {
     let prefix = prefix.into();
-    let game = GameArc::new(size, prefix.clone());
-    let (mqtt, future) = MosquittoArc::new(prefix + "-server", host, port, user);
-
-    subscribe_player(game.clone(), mqtt.clone());
-    mqtt.publish(format!("/{}/game/server", game.prefix()), "up");
+    let (host, port) = match config.embedded_broker {
+        Some(broker_port) => match Broker::start(broker_port).await {
+            Ok(_) => (String::from("localhost"), broker_port),
+            Err(error) => {
+                error!("Unable to start the embedded broker on port {}: {:?}", broker_port, error);
+                return;
+            }
+        },
+        None => (host.into(), port),
+    };
+    let (mqtt, future) = MosquittoArc::new(prefix.clone() + "-server", host, port, user);
+    if let Some(websocket_port) = config.websocket {
+        if let Err(error) = Gateway::start(websocket_port, mqtt.clone()).await {
+            error!("Unable to start the WebSocket gateway on port {}: {:?}", websocket_port, error);
+            return;
+        }
+    }
+    host_games(config, prefix, mqtt);
 
     let (result_1, _result_2, result_3) = future.await;
     result_1.unwrap();
     result_3.unwrap();
 }
 
-fn subscribe_player(mut game_arc: GameArc, mut mqtt_arc: MosquittoArc) {
+/// Hosts the game with the given prefix, and any game created later on, over any transport.
+pub fn host_games<T: Transport>(config: Config, prefix: impl Into<String>, mqtt: T) {
+    let registry = GameRegistry::new();
+
+    if let (Some(directory), true) = (&config.snapshots, config.resume) {
+        match snapshot::list(directory) {
+            Ok(prefixes) => for prefix in prefixes {
+                create_game(&registry, &mqtt, config.clone(), prefix);
+            },
+            Err(error) => error!("Unable to list snapshots in {}: {}", directory, error),
+        }
+    }
+    create_game(&registry, &mqtt, config.clone(), prefix.into());
+    subscribe_games(registry, mqtt, config);
+}
+
+fn valid_prefix(prefix: &str) -> bool {
+    !prefix.is_empty() && !prefix.contains(|character| character == '/' || character == '+' || character == '#')
+}
+
+fn subscribe_games<T: Transport>(registry: GameRegistry, mut mqtt_arc: T, config: Config) {
+    let mqtt = mqtt_arc.clone();
+    mqtt_arc.subscribe("/server/games/create", move |topic, payload| {
+        info!("Received: {} on topic {}", payload, topic);
+        let mut words = payload.split_whitespace();
+        let prefix = words.next().unwrap_or_default().to_string();
+        let mut config = config.clone();
+        if let Some(encoding) = words.next() {
+            match encoding.parse() {
+                Ok(encoding) => config.encoding = encoding,
+                Err(error) => {
+                    mqtt.publish("/server/games/error", error);
+                    return;
+                }
+            }
+        }
+        if !valid_prefix(&prefix) {
+            mqtt.publish("/server/games/error", format!("Invalid game name: {}", prefix));
+        } else if registry.get(&prefix).is_some() {
+            mqtt.publish("/server/games/error", format!("Game {} already exists", prefix));
+        } else {
+            create_game(&registry, &mqtt, config, prefix);
+        }
+    });
+}
+
+fn create_game<T: Transport>(registry: &GameRegistry, mqtt: &T, config: Config, prefix: String) {
+    if registry.get(&prefix).is_some() {
+        return;
+    }
+    info!("Hosting game {}", &prefix);
+    match restore_game(&config, &prefix) {
+        Some(mut game) => {
+            game.config = config;
+            let game = GameArc::from(game);
+            registry.insert(game.clone());
+            resume_game(game, mqtt.clone());
+        }
+        None => {
+            let size = config.size;
+            let game = GameArc::new(size, prefix.clone(), config);
+            registry.insert(game.clone());
+            let config = game.config();
+            record(&game, Event::Created { size, classes: config.classes, adjacency: config.adjacency });
+            subscribe_player(game, mqtt.clone());
+        }
+    }
+    mqtt.publish(format!("/{}/game/server", &prefix), "up");
+    if let Some(game) = registry.get(&prefix) {
+        mqtt.retain(format!("/{}/game/encoding", &prefix), game.encoding().to_string());
+        if let Ok(fleet) = serialize_with(&game.config().classes, game.encoding()) {
+            mqtt.retain(format!("/{}/game/fleet", &prefix), fleet);
+        }
+        if let Ok(size) = serialize_with(&game.size(), game.encoding()) {
+            mqtt.retain(format!("/{}/game/size", &prefix), size);
+        }
+        if let Ok(rules) = serialize_with(&Rules::from(&game.config()), game.encoding()) {
+            mqtt.retain(format!("/{}/game/rules", &prefix), rules);
+        }
+    }
+
+    if let Ok(games) = serialize(&registry.prefixes()) {
+        mqtt.retain("/server/games", games);
+    }
+}
+
+fn restore_game(config: &Config, prefix: &String) -> Option<Game> {
+    let directory = config.snapshots.as_ref()?;
+    if !config.resume {
+        return None;
+    }
+    match snapshot::load(directory, prefix) {
+        Ok(game) => Some(game),
+        Err(error) => {
+            error!("Unable to resume game {} from {}: {}", prefix, directory, error);
+            None
+        }
+    }
+}
+
+fn save_snapshot(game: &GameArc) {
+    if let Some(directory) = game.config().snapshots {
+        if let Err(error) = snapshot::save(&game.snapshot(), &directory) {
+            error!("Unable to save snapshot of game {}: {}", game.prefix(), error);
+        }
+    }
+}
+
+fn record(game: &GameArc, event: Event) {
+    if let Some(directory) = game.config().journal {
+        if let Err(error) = journal::append(&directory, &game.prefix(), &Entry::new(timestamp_millis(), event)) {
+            error!("Unable to write to the journal of game {}: {}", game.prefix(), error);
+        }
+    }
+}
+
+fn resume_game<T: Transport>(mut game: GameArc, mut mqtt: T) {
+    let state = game.state();
+    info!("Resuming game {}: {}", game.prefix(), &state);
+    let gamestate: String = state.clone().into();
+    mqtt.retain(format!("/{}/game/state", game.prefix()), &gamestate);
+    publish_players(&game, &mqtt);
+
+    for player in game.players() {
+        if let Some((_, player)) = game.find_player(&player) {
+            mqtt.retain(
+                format!("/{}/players/{}/ships/count", game.prefix(), &player.name),
+                player.active_ships().to_string()
+            );
+        }
+    }
+
+    match state {
+        GameState::Lobby => {
+            subscribe_player(game.clone(), mqtt.clone());
+            for player in game.players() {
+                subscribe_placements(&game, &mqtt, &player);
+            }
+            if game.player_count() > 0 {
+                schedule_placement_deadline(game, mqtt);
+            }
+        }
+        GameState::Underway(_, _, _) => {
+            for player in game.players() {
+                perform_salvo(game.clone(), mqtt.clone(), player.clone());
+                receive_salvo(game.clone(), mqtt.clone(), player);
+            }
+            publish_turn(&mut game, &mut mqtt);
+        }
+        GameState::Over(winner) => {
+            publish_stats(&game, &mqtt, &winner);
+            schedule_reset(game, mqtt);
+        }
+    }
+}
+
+fn subscribe_placements<T: Transport>(game: &GameArc, mqtt: &T, playername: &str) {
+    for class in game.config().classes {
+        info!("Create ship placement endpoint: {}", class);
+        place_ship(game.clone(), mqtt.clone(), playername.to_string(), class.name());
+    }
+}
+
+fn subscribe_player<T: Transport>(mut game_arc: GameArc, mut mqtt_arc: T) {
     let mqtt = mqtt_arc.clone();
     mqtt_arc.subscribe(format!("/{}/game/request", game_arc.prefix()),  move |topic, payload| {
         info!("Received: {} on topic {}", payload, topic);
-        let result: translate::Result<Player> = deserialize(payload);
+        let result: translate::Result<Player> = deserialize_with(payload, game_arc.encoding());
         match result {
             Ok(player) => {
                 let playername = player.name.clone();
-                game_arc.update_player(player);
-                for class in Class::iter() {
-                    info!("Create ship placement endpoint: {}", class);
-                    place_ship(game_arc.clone(), mqtt.clone(), playername.clone(), class.name());
-                }
+                let team = player.team.clone();
+                let config = game_arc.config();
+                game_arc.update_player(
+                    Player::new(player.name, player.secret, config.classes, game_arc.size(), config.adjacency)
+                        .in_team(player.team)
+                );
+                record(&game_arc, Event::Registered { player: playername.clone(), team });
+                subscribe_placements(&game_arc, &mqtt, &playername);
+                publish_players(&game_arc, &mqtt);
+                schedule_placement_deadline(game_arc.clone(), mqtt.clone());
+                save_snapshot(&game_arc);
             },
             Err(error) => info!("Error occured when deserializing player message: {:?}", error),
         }
-        mqtt.retain(format!("/{}/players/count", game_arc.prefix()), format!("{}", game_arc.player_count()));
+    })
+}
+
+fn publish_players<T: Transport>(game: &GameArc, mqtt: &T) {
+    mqtt.retain(format!("/{}/players/count", game.prefix()), format!("{}", game.player_count()));
+
+    if let Ok(list) = serialize_with(&game.player_list(), game.encoding()) {
+        mqtt.retain(format!("/{}/players/list", game.prefix()), list);
+    }
+
+    if let Ok(teams) = serialize_with(&game.teams(), game.encoding()) {
+        mqtt.retain(format!("/{}/players/teams", game.prefix()), teams);
+    }
+}
+
+fn timestamp() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs())
+}
+
+fn timestamp_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_millis() as u64)
+}
+
+fn schedule_placement_deadline<T: Transport>(mut game: GameArc, mqtt: T) {
+    let timeout = game.config().placement_timeout;
+    let deadline = timestamp() + timeout;
+    game.set_placement_deadline(Some(deadline));
+    mqtt.retain(format!("/{}/game/placement_deadline", game.prefix()), deadline.to_string());
+
+    tokio::spawn(async move {
+        sleep(Duration::from_secs(timeout)).await;
+        // A later registration moves the deadline, in which case a newer timer takes over.
+        if game.state() == GameState::Lobby && game.placement_deadline() == Some(deadline) {
+            placement_deadline_passed(game, mqtt);
+        }
+    });
+}
 
-        match serialize(&game_arc.player_list()) {
-            Ok(list) => mqtt.retain(format!("/{}/players/list", game_arc.prefix()), list),
-            _ => {}
+fn placement_deadline_passed<T: Transport>(mut game: GameArc, mut mqtt: T) {
+    info!("Placement deadline passed for game {}", game.prefix());
+    let stragglers = game.config().stragglers;
+    for straggler in game.incomplete_players() {
+        let player = match stragglers {
+            StragglerPolicy::RandomFleet => straggler.complete_fleet(&mut |size| rand::thread_rng().gen_range(0..size)),
+            StragglerPolicy::Drop => straggler.clone(),
         };
-    })
+
+        if player.is_fleet_complete() {
+            info!("Generated a random fleet for {}", player.name);
+            for ship in player.ships().into_iter().filter(|ship| !straggler.placed(&ship.class)) {
+                record(&game, Event::Placed { player: player.name.clone(), ship });
+            }
+            mqtt.retain(
+                format!("/{}/players/{}/ships/count", game.prefix(), &player.name),
+                player.active_ships().to_string()
+            );
+            game.update_player(player);
+        } else {
+            info!("Dropping {} from the game", player.name);
+            for class in game.config().classes {
+                mqtt.clear(format!("/{}/players/{}/ships/{}/approved", game.prefix(), &player.name, &class));
+                mqtt.unsubscribe(format!("/{}/players/{}/ships/{}/place", game.prefix(), &player.name, &class));
+            }
+            mqtt.clear(format!("/{}/players/{}/ships/count", game.prefix(), &player.name));
+            mqtt.publish(
+                format!("/{}/players/{}/error", game.prefix(), &player.name),
+                format!("{}", GameError::PlacementDeadlinePassed)
+            );
+            game.remove_player(&player.name);
+            record(&game, Event::Dropped { player: player.name.clone() });
+        }
+    }
+    publish_players(&game, &mqtt);
+
+    if game.active_teams().len() > 1 {
+        save_snapshot(&game);
+        start_game(game, mqtt);
+    } else {
+        // Back to an open lobby, the next registration sets a new deadline.
+        info!("Not enough teams with a complete fleet, waiting for more players.");
+        game.set_placement_deadline(None);
+        mqtt.clear(format!("/{}/game/placement_deadline", game.prefix()));
+        let gamestate: String = game.state().into();
+        mqtt.retain(format!("/{}/game/state", game.prefix()), &gamestate);
+        save_snapshot(&game);
+    }
 }
 
-fn place_ship(
-    mut game_arc: GameArc, mut mqtt_arc: MosquittoArc, playername: String, class: String
+fn place_ship<T: Transport>(
+    mut game_arc: GameArc, mut mqtt_arc: T, playername: String, class: String
 ) {
     let mqtt = mqtt_arc.clone();
     mqtt_arc.subscribe(format!("/{}/players/{}/ships/{}/place", game_arc.prefix(), &playername, class), move |topic, payload| {
@@ -74,13 +356,26 @@ fn place_ship(
             Some((_, player)) => player,
             _ => return,
         };
-        //
-        // let payload = match decrypt(&player.secret, payload) {
-        //     Ok(payload) => payload,
-        //     _ => return,
-        // };
 
-        let ship: Ship = match Ship::from_json(payload, class.as_str().into()) {
+        let payload = match decrypt(payload, &player.secret) {
+            Ok(payload) => payload,
+            Err(_) if game.config().allow_plaintext => payload.clone(),
+            Err(error) => {
+                info!("Unable to decrypt ship placement of {}: {:?}", &playername, error);
+                mqtt.publish(
+                    format!("/{}/players/{}/ships/{}/error", game.prefix(), &playername, &class),
+                    format!("{}", GameError::PlacementNotDecryptable)
+                );
+                return;
+            }
+        };
+
+        let ship_class = match player.class(&class) {
+            Some(ship_class) => ship_class,
+            None => return,
+        };
+
+        let ship: Ship = match Ship::from_payload(payload, ship_class, game.encoding()) {
             Ok(ship) => ship,
             Err(error) => {
                 mqtt.publish(
@@ -91,20 +386,22 @@ fn place_ship(
             }
         };
 
-        match player.place_ship(ship) {
+        match player.place_ship(ship.clone()) {
             Ok(player) => {
+                record(game, Event::Placed { player: playername.clone(), ship });
                 mqtt.retain(
                     format!("/{}/players/{}/ships/count", game.prefix(), &playername),
                     &player.active_ships().to_string()
                 );
                 game.update_player(player);
+                save_snapshot(game);
                 mqtt.retain(
                     format!("/{}/players/{}/ships/{}/approved", game.prefix(), &playername, &class),
                     "true"
                 );
                 print!("{} has successfully placed {}.", playername, class);
 
-                if game.ready_player_count() == game.player_count() {
+                if game.ready_player_count() == game.player_count() && game.active_teams().len() > 1 {
                     start_game(game_arc.clone(), mqtt.clone())
                 }
             }
@@ -118,61 +415,121 @@ fn place_ship(
     });
 }
 
-fn next_turn(game: &mut GameArc, mqtt: &mut MosquittoArc) {
+fn next_turn<T: Transport>(game: &mut GameArc, mqtt: &mut T) {
+    game.update_stats(|stats| stats.turn_ended(timestamp_millis()));
     game.next_turn();
+    if let Underway((_index, player), _, _) = game.state() {
+        record(game, Event::TurnChanged { player: player.clone() });
+        let active_players = game.active_players();
+        game.update_stats(|stats| stats.turn_started(&player, &active_players, timestamp_millis()));
+    }
+    publish_turn(game, mqtt);
+    save_snapshot(game);
+}
+
+fn publish_turn<T: Transport>(game: &mut GameArc, mqtt: &mut T) {
     if let Underway((_index, player), fired_shots, _hits) = game.state() {
+        let deadline = schedule_turn_deadline(game.clone(), mqtt.clone());
+        let shots = game.find_player(&player).map_or(0, |(_, player)| player.allowance(game.config().salvo_size));
         mqtt.retain(format!("/{}/game/fired_shots", game.prefix()), format!("{}", fired_shots));
-        mqtt.retain(format!("/{}/game/current", game.prefix()), &player);
+        if let Ok(turn) = serialize_with(&Turn::new(player, deadline, shots), game.encoding()) {
+            mqtt.retain(format!("/{}/game/current", game.prefix()), turn);
+        }
+    }
+}
+
+fn schedule_turn_deadline<T: Transport>(game: GameArc, mqtt: T) -> u64 {
+    let timeout = game.config().turn_timeout;
+    let turn = game.turn();
+
+    tokio::spawn(async move {
+        sleep(Duration::from_secs(timeout)).await;
+        if game.turn() == turn {
+            turn_timed_out(game, mqtt);
+        }
+    });
+    timestamp() + timeout
+}
+
+fn turn_timed_out<T: Transport>(mut game: GameArc, mut mqtt: T) {
+    let (current_player, fired_shots) = match game.state() {
+        GameState::Underway((_, player), fired_shots, _) => (player, fired_shots),
+        _ => return,
+    };
+    let mut player = match game.find_player(&current_player) {
+        Some((_, player)) => player.timed_out(),
+        _ => return,
+    };
+    record(&game, Event::TimedOut { player: player.name.clone() });
+    info!(
+        "{} ran out of time, forfeiting {} shots ({} timeouts)",
+        &player.name, player.allowance(game.config().salvo_size).saturating_sub(fired_shots), player.timeouts()
+    );
+
+    let max_timeouts = game.config().max_timeouts;
+    if max_timeouts > 0 && player.timeouts() >= max_timeouts {
+        info!("Player {} timed out too often and is now defeated!", &player.name);
+        player = player.forfeit();
+        game.update_stats(|stats| stats.eliminated(&player.name));
+        record(&game, Event::Defeated { player: player.name.clone() });
+        mqtt.publish(format!("/{}/players/{}/defeated", game.prefix(), &player.name), "true");
+    }
+    game.update_player(player);
+    salvo_completed(&mut game, &mqtt);
+
+    if game.active_teams().len() <= 1 {
+        game_over(&mut game, &mut mqtt);
+    } else {
+        next_turn(&mut game, &mut mqtt);
     }
 }
 
-fn start_game(mut game: GameArc, mut mqtt: MosquittoArc) {
+fn start_game<T: Transport>(mut game: GameArc, mut mqtt: T) {
+    if !game.start(|size| rand::thread_rng().gen_range(0..size)) {
+        return;
+    }
     mqtt.unsubscribe(format!("/{}/game/request", game.prefix()));
+    mqtt.clear(format!("/{}/game/placement_deadline", game.prefix()));
     for player in game.players() {
-        for class in Class::iter() {
+        for class in game.config().classes {
             mqtt.clear(format!("/{}/players/{}/ships/{}/approved", game.prefix(), &player, &class));
             mqtt.unsubscribe(format!("/{}/players/{}/ships/{}/place", game.prefix(), player, class));
         }
     }
-    game.start(|size| rand::thread_rng().gen_range(0..size));
     let gamestate: String = game.state().into();
     mqtt.retain(format!("/{}/game/state", game.prefix()), &gamestate);
     next_turn(&mut game, &mut mqtt);
     for player in game.players() {
-        perform_salvo(game.clone(), mqtt.clone(), player);
+        perform_salvo(game.clone(), mqtt.clone(), player.clone());
+        receive_salvo(game.clone(), mqtt.clone(), player);
     }
 }
 
-fn perform_salvo(mut game_arc: GameArc, mut mqtt_arc: MosquittoArc, target_player: String) {
+fn perform_salvo<T: Transport>(mut game_arc: GameArc, mut mqtt_arc: T, target_player: String) {
     let mut mqtt = mqtt_arc.clone();
     mqtt_arc.subscribe(format!("/{}/players/{}/fire", game_arc.prefix(), &target_player), move |topic, payload| {
         assert!(format!("/{}/players/{}/fire", game_arc.prefix(), &target_player) == *topic);
         info!("Received shot on topic {}, coordinates: {}", topic, payload);
 
-        let mut game = &mut game_arc;
+        let game = &mut game_arc;
 
-        let (current_player, fired_shots) = match game.state() {
-            GameState::Underway((_, player), fired_shots, _) => (player, fired_shots),
+        let current_player = match game.state() {
+            GameState::Underway((_, player), _, _) => player,
             _ => return,
         };
 
-        if current_player == target_player {
-            info!("You shootn' yourself there!?");
-        }
-
         let current_player = match game.find_player(current_player) {
             Some((_, current_player)) => current_player,
             _ => return,
         };
 
-        // let coordinates: Point = match translate::verify(payload, &current_player.secret) {
-        //     Ok(payload) => payload,
-        //     _ => return,
-        // };
-
-        let coordinates: Point = match deserialize(payload) {
+        let coordinates: Point = match translate::verify_with(payload, &current_player.secret, game.encoding()) {
             Ok(coordinates) => coordinates,
-            _ => return,
+            Err(error) => {
+                // Anyone can publish on the fire topic, so the error cannot be sent back to whoever did.
+                info!("Dropped shot on topic {}, not signed by {}: {:?}", topic, &current_player.name, error);
+                return;
+            }
         };
 
         let target_player = match game.find_player(&target_player) {
@@ -180,70 +537,374 @@ fn perform_salvo(mut game_arc: GameArc, mut mqtt_arc: MosquittoArc, target_playe
             _ => return,
         };
 
-        if let Ok(json) = serialize(&coordinates) {
-            mqtt.publish(format!("/{}/players/{}/hit", game.prefix(), &target_player.name), json);
+        let verdict = match judge_shot(game, &current_player, &target_player, &coordinates) {
+            Ok(verdict) => verdict,
+            Err(error) => {
+                reject_shot(game, &mqtt, &current_player.name, error);
+                return;
+            }
+        };
+
+        let (result, decided) = match resolve_shot(game, &mqtt, &current_player.name, &target_player.name, coordinates, verdict) {
+            Some(resolution) => resolution,
+            None => return,
+        };
+        if game.config().disclosure == Disclosure::Classic {
+            if let Ok(result) = serialize_with(&result, game.encoding()) {
+                mqtt.publish(format!("/{}/players/{}/shots", game.prefix(), &current_player.name), result);
+            }
+        }
+
+        if decided {
+            salvo_completed(game, &mqtt);
+            game_over(game, &mut mqtt);
+            return;
         }
 
-        match target_player.shoot(&coordinates) {
-            Impact::Miss => info!("That's a miss!"),
-            Impact::Hit((updated_target_player, hit_ship)) => {
-                info!(
-                    "That's a hit! {} hit {}'s {}", &current_player.name, &target_player.name,
-                    hit_ship.class
-                );
-                let target_player = updated_target_player.clone();
-                game.update_player(updated_target_player);
-
-                if hit_ship.is_sunk() {
-                    info!("Player {}'s {} sunk!", target_player.name, hit_ship.class);
-                    mqtt.publish(
-                        format!("/{}/players/{}/ships/{}/sunk", game.prefix(), target_player.name, hit_ship.class),
-                        "true"
-                    );
-                    mqtt.retain(
-                        format!("/{}/players/{}/ships/count", game.prefix(), target_player.name),
-                        target_player.active_ships().to_string()
-                    );
-
-                    if target_player.is_defeated() {
-                        info!("Player {}'s is now defeated!", target_player.name);
-                        mqtt.publish(format!("/{}/players/{}/defeated", game.prefix(), target_player.name), "true");
-
-                        if game.active_player_count() == 1 {
-                            game_over(game, &mut mqtt);
-                            return;
-                        }
-                    }
+        let fired_shots = game.fired_shots();
+        mqtt.retain(format!("/{}/game/fired_shots", game.prefix()), format!("{}", fired_shots));
+
+        if fired_shots >= current_player.allowance(game.config().salvo_size) {
+            salvo_completed(game, &mqtt);
+            next_turn(game, &mut mqtt);
+        } else {
+            save_snapshot(game);
+        }
+    });
+}
+
+fn receive_salvo<T: Transport>(mut game_arc: GameArc, mut mqtt_arc: T, shooter: String) {
+    let mut mqtt = mqtt_arc.clone();
+    mqtt_arc.subscribe(format!("/{}/players/{}/salvo", game_arc.prefix(), &shooter), move |topic, payload| {
+        assert!(format!("/{}/players/{}/salvo", game_arc.prefix(), &shooter) == *topic);
+        info!("Received salvo on topic {}: {}", topic, payload);
+
+        let game = &mut game_arc;
+
+        let (current_player, fired_shots) = match game.state() {
+            GameState::Underway((_, player), fired_shots, _) => (player, fired_shots),
+            _ => return,
+        };
+
+        let player = match game.find_player(&shooter) {
+            Some((_, player)) => player,
+            _ => return,
+        };
+
+        let shots: Vec<SalvoShot> = match translate::verify_with(payload, &player.secret, game.encoding()) {
+            Ok(shots) => shots,
+            Err(error) => {
+                info!("Rejected salvo by {}: {:?}", &player.name, error);
+                reject_shot(game, &mqtt, &player.name, payload_error(error));
+                return;
+            }
+        };
+
+        if current_player != player.name {
+            reject_shot(game, &mqtt, &player.name, GameError::NotYourTurn);
+            return;
+        }
+
+        let remaining = player.allowance(game.config().salvo_size).saturating_sub(fired_shots);
+        if shots.len() != remaining as usize {
+            reject_shot(game, &mqtt, &player.name, GameError::WrongSalvoSize(remaining, shots.len()));
+            return;
+        }
+
+        // Every shot is judged before any of them is resolved, so a salvo is either accepted or rejected as a whole.
+        let mut shooter = player.clone();
+        let mut verdicts = vec![];
+        for shot in &shots {
+            let target = match game.find_player(&shot.target) {
+                Some((_, target)) => target,
+                None => {
+                    reject_shot(game, &mqtt, &player.name, GameError::UnknownPlayer(shot.target.clone()));
+                    return;
+                }
+            };
+            match judge_shot(game, &shooter, &target, &shot.coordinates()) {
+                Ok(verdict) => verdicts.push(verdict),
+                Err(error) => {
+                    reject_shot(game, &mqtt, &player.name, error);
+                    return;
                 }
             }
+            shooter.fired_at(&target.name, shot.coordinates());
+        }
+
+        let mut results = vec![];
+        let mut decided = false;
+        for (shot, verdict) in shots.iter().zip(verdicts) {
+            if let Some((result, game_decided)) = resolve_shot(game, &mqtt, &player.name, &shot.target, shot.coordinates(), verdict) {
+                results.push(result);
+                decided = game_decided;
+            }
+            if decided {
+                break;
+            }
+        }
+
+        let result = SalvoResult {
+            shots: results.len() as u8,
+            hits: results.iter().filter(|result| result.hit).count() as u8,
+            results: if game.config().disclosure == Disclosure::Classic {
+                results
+            } else {
+                vec![]
+            },
         };
+        if let Ok(result) = serialize_with(&result, game.encoding()) {
+            mqtt.publish(format!("/{}/players/{}/salvo/result", game.prefix(), &player.name), result);
+        }
 
-        mqtt.retain(format!("/{}/game/fired_shots", game.prefix()), format!("{}", fired_shots));
+        salvo_completed(game, &mqtt);
+        if decided {
+            game_over(game, &mut mqtt);
+        } else {
+            mqtt.retain(format!("/{}/game/fired_shots", game.prefix()), format!("{}", game.fired_shots()));
+            next_turn(game, &mut mqtt);
+        }
+    });
+}
+
+/// How a shot that passed the rules is resolved: against the target's fleet, as a miss, or as a miss with an error.
+enum Verdict {
+    Resolve,
+    Spare,
+    Waste(GameError),
+}
+
+fn judge_shot(game: &GameArc, shooter: &Player, target: &Player, coordinates: &Point) -> Result<Verdict, GameError> {
+    if !game.size().contains(coordinates) {
+        return Err(GameError::ShotOutOfBounds(*coordinates));
+    }
+
+    let config = game.config();
+    let questionable_target = if target.name == shooter.name {
+        Some((config.self_targeting, GameError::SelfTargeted))
+    } else if target.is_defeated() {
+        Some((config.defeated_targets, GameError::TargetDefeated(target.name.clone())))
+    } else {
+        None
+    };
+    if let Some((policy, error)) = questionable_target {
+        match policy {
+            TargetPolicy::Allow => {}
+            TargetPolicy::Reject => return Err(error),
+            TargetPolicy::RejectAndConsume => return Ok(Verdict::Waste(error)),
+        }
+    }
+
+    if shooter.teammate(target) {
+        match config.friendly_fire {
+            FriendlyFire::Allowed => {}
+            FriendlyFire::Rejected => return Err(GameError::FriendlyFire(target.name.clone())),
+            FriendlyFire::Ignored => return Ok(Verdict::Spare),
+        }
+    }
 
-        if fired_shots >= current_player.active_ships() as u8 {
-            next_turn(&mut game, &mut mqtt);
+    if config.reject_repeats && shooter.has_fired_at(&target.name, coordinates) {
+        return Err(GameError::RepeatedShot(target.name.clone(), *coordinates));
+    }
+    Ok(Verdict::Resolve)
+}
+
+/// Resolves a shot that passed the rules, returns its result and whether it decided the game.
+fn resolve_shot<T: Transport>(
+    game: &mut GameArc, mqtt: &T, shooter: &String, target: &String, coordinates: Point, verdict: Verdict
+) -> Option<(ShotResult, bool)> {
+    let current_player = game.find_player(shooter)?.1;
+    let target_player = game.find_player(target)?.1;
+    let repeated = current_player.has_fired_at(target, &coordinates);
+
+    let impact = match verdict {
+        Verdict::Resolve => {
+            if game.config().disclosure == Disclosure::Classic {
+                if let Ok(point) = serialize_with(&coordinates, game.encoding()) {
+                    mqtt.publish(format!("/{}/players/{}/hit", game.prefix(), &target_player.name), point);
+                }
+            }
+            target_player.shoot(&coordinates)
+        }
+        // Ignored and wasted shots only use up a shot, they are not part of the history, the statistics or the journal.
+        Verdict::Spare => {
+            info!("{}'s shot at {} is ignored", shooter, target);
+            game.consume_shot();
+            return Some((ShotResult::new(target.clone(), coordinates, false), false));
         }
+        Verdict::Waste(error) => {
+            reject_shot(game, mqtt, shooter, error);
+            game.consume_shot();
+            return Some((ShotResult::new(target.clone(), coordinates, false), false));
+        }
+    };
+    let hit = matches!(impact, Impact::Hit(_));
+    game.register_shot(&target_player.name, hit);
+    game.update_stats(|stats| stats.shot(&current_player.name, hit, repeated));
+    record(game, Event::Shot {
+        shooter: current_player.name.clone(),
+        target: target_player.name.clone(),
+        coordinates,
+        impact: match &impact {
+            Impact::Hit((_, ship)) => Impact::Hit(ship.clone()),
+            Impact::Miss => Impact::Miss,
+        },
     });
+    if let Impact::Hit((updated_target_player, _)) = &impact {
+        game.update_player(updated_target_player.clone());
+    }
+    game.remember_shot(&current_player.name, &target_player.name, coordinates);
+
+    let mut decided = false;
+    match impact {
+        Impact::Miss => info!("That's a miss!"),
+        Impact::Hit((target_player, hit_ship)) => {
+            info!(
+                "That's a hit! {} hit {}'s {}", &current_player.name, &target_player.name,
+                hit_ship.class
+            );
+
+            if hit_ship.is_sunk() {
+                info!("Player {}'s {} sunk!", target_player.name, hit_ship.class);
+                game.update_stats(|stats| stats.sunk(&current_player.name));
+                record(game, Event::Sunk { player: target_player.name.clone(), class: hit_ship.class.clone() });
+
+                if target_player.is_defeated() {
+                    info!("Player {}'s is now defeated!", target_player.name);
+                    game.update_stats(|stats| stats.eliminated(&target_player.name));
+                    record(game, Event::Defeated { player: target_player.name.clone() });
+                }
+
+                if game.config().disclosure == Disclosure::Classic {
+                    announce_sink(game, mqtt, &target_player.name, &hit_ship.class);
+                } else {
+                    game.register_sink(&target_player.name, &hit_ship.class);
+                }
+
+                decided = target_player.is_defeated() && game.active_teams().len() <= 1;
+            }
+        }
+    };
+    Some((ShotResult::new(target.clone(), coordinates, hit), decided))
 }
 
-fn game_over(game: &mut GameArc, mqtt: &mut MosquittoArc) {
+fn announce_sink<T: Transport>(game: &GameArc, mqtt: &T, player: &String, class: &Class) {
+    let player = match game.find_player(player) {
+        Some((_, player)) => player,
+        _ => return,
+    };
+    if game.config().disclosure == Disclosure::HiddenSink {
+        mqtt.publish(format!("/{}/players/{}/sunk", game.prefix(), &player.name), "true");
+    } else {
+        mqtt.publish(format!("/{}/players/{}/ships/{}/sunk", game.prefix(), &player.name, class), "true");
+    }
+    mqtt.retain(
+        format!("/{}/players/{}/ships/count", game.prefix(), &player.name),
+        player.active_ships().to_string()
+    );
+    if player.is_defeated() {
+        mqtt.publish(format!("/{}/players/{}/defeated", game.prefix(), &player.name), "true");
+    }
+}
+
+fn salvo_completed<T: Transport>(game: &mut GameArc, mqtt: &T) {
+    for (player, class) in game.take_sinks() {
+        announce_sink(game, mqtt, &player, &class);
+    }
+    if let GameState::Underway((_, player), _, hits) = game.state() {
+        info!("{}'s salvo resulted in {} hits", player, hits);
+        mqtt.publish(format!("/{}/game/hits", game.prefix()), format!("{}", hits));
+
+        if game.config().hits_breakdown {
+            if let Ok(targets) = serialize_with(&game.salvo(), game.encoding()) {
+                mqtt.publish(format!("/{}/game/hits/targets", game.prefix()), targets);
+            }
+        }
+    }
+}
+
+fn payload_error(error: translate::TranslationError) -> GameError {
+    match error {
+        translate::TranslationError::VerificationError(_) => GameError::InvalidSignature,
+        _ => GameError::MalformedPayload,
+    }
+}
+
+fn reject_shot<T: Transport>(game: &GameArc, mqtt: &T, shooter: &String, error: GameError) {
+    mqtt.publish(format!("/{}/players/{}/error", game.prefix(), shooter), format!("{}", error));
+}
+
+fn game_over<T: Transport>(game: &mut GameArc, mqtt: &mut T) {
 
     for player in game.players() {
         mqtt.clear(format!("/{}/players/{}/ships/count", game.prefix(), &player));
         mqtt.unsubscribe(format!("/{}/players/{}/fire", game.prefix(), &player));
+        mqtt.unsubscribe(format!("/{}/players/{}/salvo", game.prefix(), &player));
     }
 
     mqtt.clear(format!("/{}/game/state", game.prefix()));
     mqtt.clear(format!("/{}/players/count", game.prefix()));
     mqtt.clear(format!("/{}/players/list", game.prefix()));
+    mqtt.clear(format!("/{}/players/teams", game.prefix()));
     mqtt.clear(format!("/{}/game/fired_shots", game.prefix()));
     mqtt.clear(format!("/{}/game/current", game.prefix()));
 
+    game.update_stats(|stats| stats.turn_ended(timestamp_millis()));
     game.game_over();
     let state = game.state();
     mqtt.publish(format!("/{}/game/state", game.prefix()), format!("{}", &state));
 
     if let GameState::Over(winner) = &state {
-        mqtt.publish(format!("/{}/game/winner", game.prefix()), winner);
+        if let Some(Ok(winner)) = game.winner().map(|winner| serialize_with(&winner, game.encoding())) {
+            mqtt.publish(format!("/{}/game/winner", game.prefix()), winner);
+        }
+        record(game, Event::GameOver { winner: winner.clone() });
+        publish_stats(game, mqtt, winner);
+    }
+    save_snapshot(game);
+    schedule_reset(game.clone(), mqtt.clone());
+}
+
+fn schedule_reset<T: Transport>(game: GameArc, mqtt: T) {
+    let cool_down = game.config().cool_down;
+    tokio::spawn(async move {
+        sleep(Duration::from_secs(cool_down)).await;
+        reset_game(game, mqtt);
+    });
+}
+
+fn publish_stats<T: Transport>(game: &GameArc, mqtt: &T, winner: &str) {
+    let stats = game.stats();
+    for (player, player_stats) in &stats.players {
+        if let Ok(player_stats) = serialize_with(player_stats, game.encoding()) {
+            mqtt.retain(format!("/{}/game/stats/{}", game.prefix(), player), player_stats);
+        }
+    }
+    if let Ok(summary) = serialize_with(&stats.summary(Some(winner.to_string())), game.encoding()) {
+        mqtt.retain(format!("/{}/game/stats/summary", game.prefix()), summary);
+    }
+}
+
+fn reset_game<T: Transport>(mut game: GameArc, mqtt: T) {
+    if let GameState::Over(_) = game.state() {
+        info!("Resetting game {} to the lobby", game.prefix());
+        for player in game.players() {
+            mqtt.clear(format!("/{}/players/{}/ships/count", game.prefix(), &player));
+        }
+        for player in game.stats().players.keys() {
+            mqtt.clear(format!("/{}/game/stats/{}", game.prefix(), player));
+        }
+        mqtt.clear(format!("/{}/game/stats/summary", game.prefix()));
+        mqtt.clear(format!("/{}/game/placement_deadline", game.prefix()));
+        mqtt.clear(format!("/{}/game/fired_shots", game.prefix()));
+        mqtt.clear(format!("/{}/game/current", game.prefix()));
+
+        game.reset();
+        record(&game, Event::Reset);
+        publish_players(&game, &mqtt);
+        let gamestate: String = game.state().into();
+        mqtt.retain(format!("/{}/game/state", game.prefix()), &gamestate);
+        save_snapshot(&game);
+        subscribe_player(game, mqtt);
     }
 }
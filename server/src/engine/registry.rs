@@ -0,0 +1,43 @@
+use crate::model::game::GameArc;
+use std::collections::BTreeMap;
+use std::collections::btree_map::Entry;
+use std::sync::{Arc, Mutex};
+
+pub struct GameRegistry {
+    games: Arc<Mutex<BTreeMap<String, GameArc>>>
+}
+
+impl GameRegistry {
+    pub fn new() -> GameRegistry {
+        GameRegistry {
+            games: Arc::new(Mutex::new(BTreeMap::new())),
+        }
+    }
+
+    pub fn insert(&self, game: GameArc) -> bool {
+        let mut games = self.games.lock().unwrap();
+        match games.entry(game.prefix()) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                entry.insert(game);
+                true
+            }
+        }
+    }
+
+    pub fn get(&self, prefix: &String) -> Option<GameArc> {
+        self.games.lock().unwrap().get(prefix).cloned()
+    }
+
+    pub fn prefixes(&self) -> Vec<String> {
+        self.games.lock().unwrap().keys().cloned().collect()
+    }
+}
+
+impl Clone for GameRegistry {
+    fn clone(&self) -> Self {
+        GameRegistry {
+            games: Arc::clone(&self.games),
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use crate::model::game::Game;
+use crate::messaging::translate::{self, serialize, deserialize, TranslationError};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::fmt::{self, Display, Formatter};
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    IoError(io::Error),
+    TranslationError(TranslationError),
+}
+
+impl Display for SnapshotError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::IoError(error) => write!(f, "{}", error),
+            SnapshotError::TranslationError(error) => write!(f, "{:?}", error),
+        }
+    }
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(error: io::Error) -> Self {
+        SnapshotError::IoError(error)
+    }
+}
+
+impl From<TranslationError> for SnapshotError {
+    fn from(error: TranslationError) -> Self {
+        SnapshotError::TranslationError(error)
+    }
+}
+
+fn path(directory: &str, prefix: &str) -> PathBuf {
+    Path::new(directory).join(format!("{}.json", prefix))
+}
+
+pub fn save(game: &Game, directory: &str) -> Result<(), SnapshotError> {
+    fs::create_dir_all(directory)?;
+    let json = serialize(game)?;
+    let path = path(directory, &game.prefix);
+    // Write next to the snapshot first, so a crash halfway never leaves a truncated snapshot behind.
+    let temporary = path.with_extension("json.tmp");
+    fs::write(&temporary, json)?;
+    fs::rename(temporary, path)?;
+    Ok(())
+}
+
+pub fn list(directory: &str) -> Result<Vec<String>, SnapshotError> {
+    let mut prefixes = vec![];
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.extension().map_or(false, |extension| extension == "json") {
+            if let Some(prefix) = path.file_stem() {
+                prefixes.push(prefix.to_string_lossy().into());
+            }
+        }
+    }
+    Ok(prefixes)
+}
+
+pub fn load(directory: &str, prefix: &str) -> Result<Game, SnapshotError> {
+    let json = fs::read_to_string(path(directory, prefix))?;
+    let game: translate::Result<Game> = deserialize(&json);
+    Ok(game?)
+}
@@ -0,0 +1,500 @@
+#[cfg(test)]
+mod tests {
+    use crate::engine::host_games;
+    use crate::messaging::memory::MemoryBus;
+    use crate::messaging::transport::Transport;
+    use crate::messaging::protobuf;
+    use crate::messaging::translate::{deserialize, serialize_with, sign, sign_with};
+    use crate::model::{
+        adjacency::Adjacency, class::Class, config::Config, disclosure::Disclosure, encoding::Encoding,
+        event::{Entry, Event}, friendlyfire::FriendlyFire, game::Game, gameerror::GameError, gamestate::GameState,
+        orientation::Orientation, player::Player, point::Point, salvo::SalvoShot, ship::Ship, size::Size,
+        targetpolicy::TargetPolicy, turn::Turn, winner::Winner,
+    };
+    use prost::Message;
+    use std::fs;
+    use std::path::Path;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tokio::time::sleep;
+
+    fn config() -> Config {
+        Config {
+            allow_plaintext: true,
+            classes: vec![Class::new("patrolboat", 2, "P")],
+            size: Size::new(4, 4),
+            ..Config::default()
+        }
+    }
+
+    fn register(bus: &MemoryBus, name: &str, team: Option<&str>) {
+        let team = team.map_or(String::new(), |team| format!(", \"team\": \"{}\"", team));
+        bus.publish(
+            "/test/game/request",
+            format!("{{\"name\": \"{}\", \"secret\": \"{}-secret\"{}}}", name, name, team),
+        );
+    }
+
+    fn place(bus: &MemoryBus, name: &str) {
+        bus.publish(
+            format!("/test/players/{}/ships/patrolboat/place", name),
+            "{\"coordinates\": {\"x\": 0, \"y\": 0}, \"orientation\": \"Horizontal\"}",
+        );
+    }
+
+    fn join(bus: &MemoryBus, name: &str) {
+        register(bus, name, None);
+        place(bus, name);
+    }
+
+    fn current_player(bus: &MemoryBus) -> String {
+        let current: Turn = deserialize(&bus.retained("/test/game/current").expect("No current player")).unwrap();
+        current.player
+    }
+
+    fn fire(bus: &MemoryBus, target: &str, x: u8, y: u8) {
+        let shooter = current_player(bus);
+        bus.publish(
+            format!("/test/players/{}/fire", target),
+            sign(Point::new(x, y), format!("{}-secret", shooter)).unwrap(),
+        );
+    }
+
+    /// Hosts a game with the given rules, registers the players with their teams, then places their fleets.
+    fn start_with(config: Config, players: &[(&str, Option<&str>)]) -> MemoryBus {
+        let bus = MemoryBus::new();
+        host_games(config, "test", bus.clone());
+        for (name, team) in players {
+            register(&bus, name, *team);
+        }
+        for (name, _) in players {
+            place(&bus, name);
+        }
+        bus
+    }
+
+    /// Hosts a game with the given rules and starts it with henkie and bassie.
+    fn start(config: Config) -> MemoryBus {
+        start_with(config, &[("henkie", None), ("bassie", None)])
+    }
+
+    fn other(player: &str) -> String {
+        if player == "henkie" { "bassie" } else { "henkie" }.to_string()
+    }
+
+    /// Plays a started game to its end, returns the winner.
+    fn play(bus: &MemoryBus) -> String {
+        let first = current_player(bus);
+        fire(bus, &other(&first), 0, 0);
+        fire(bus, &first, 3, 3);
+        fire(bus, &other(&first), 1, 0);
+        first
+    }
+
+    #[tokio::test]
+    async fn test_game_over_memory_bus() {
+        let mut bus = MemoryBus::new();
+        let winner = Arc::new(Mutex::new(None));
+        let winner_for_callback = winner.clone();
+        bus.subscribe("/test/game/winner", move |_, payload| {
+            *winner_for_callback.lock().unwrap() = Some(payload.clone());
+        });
+
+        host_games(config(), "test", bus.clone());
+        join(&bus, "henkie");
+        join(&bus, "bassie");
+        assert_eq!(bus.retained("/test/game/state"), Some("underway".to_string()));
+
+        let first = current_player(&bus);
+        let second = if first == "henkie" { "bassie" } else { "henkie" }.to_string();
+        fire(&bus, &second, 0, 0);
+        assert_eq!(current_player(&bus), second);
+        fire(&bus, &first, 3, 3);
+        fire(&bus, &second, 1, 0);
+
+        let winner: Winner = deserialize(&winner.lock().unwrap().clone().expect("No winner")).unwrap();
+        assert_eq!(winner, Winner::new(first.clone(), vec![first]));
+        assert_eq!(bus.retained("/test/game/current"), None);
+        assert!(bus.retained("/test/game/stats/summary").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_rejected_shot_memory_bus() {
+        let mut bus = MemoryBus::new();
+        host_games(config(), "test", bus.clone());
+        join(&bus, "henkie");
+        join(&bus, "bassie");
+
+        let first = current_player(&bus);
+        let errors = Arc::new(Mutex::new(vec![]));
+        let errors_for_callback = errors.clone();
+        bus.subscribe(format!("/test/players/{}/error", &first), move |_, payload| {
+            errors_for_callback.lock().unwrap().push(payload.clone());
+        });
+
+        fire(&bus, "henkie", 4, 0);
+        assert_eq!(errors.lock().unwrap().len(), 1);
+        assert_eq!(current_player(&bus), first);
+    }
+
+    #[tokio::test]
+    async fn test_forged_shot_memory_bus() {
+        let mut bus = start(config());
+        let errors = collect(&mut bus, "/test/players/+/error");
+        let first = current_player(&bus);
+        let second = other(&first);
+
+        bus.publish(
+            format!("/test/players/{}/fire", &second),
+            sign(Point::new(0, 0), format!("{}-secret", &second)).unwrap(),
+        );
+        bus.publish(format!("/test/players/{}/fire", &second), "{\"x\": 0, \"y\": 0}");
+        assert!(errors.lock().unwrap().is_empty());
+        assert_eq!(bus.retained("/test/game/fired_shots"), Some("0".to_string()));
+        assert_eq!(current_player(&bus), first);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_registration_keeps_deadline() {
+        let bus = MemoryBus::new();
+        host_games(config(), "test", bus.clone());
+        bus.publish("/test/game/request", "{\"name\": \"henkie\", \"secret\": \"henkie-secret\"}");
+        assert!(bus.retained("/test/game/placement_deadline").is_some());
+
+        bus.clear("/test/game/placement_deadline");
+        bus.publish("/test/game/request", "garbage");
+        assert_eq!(bus.retained("/test/game/placement_deadline"), None);
+        assert_eq!(bus.retained("/test/players/count"), Some("1".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_deadline_without_opponents_reopens_lobby() {
+        let bus = MemoryBus::new();
+        host_games(Config { placement_timeout: 0, ..config() }, "test", bus.clone());
+        join(&bus, "henkie");
+
+        sleep(Duration::from_millis(100)).await;
+        assert_eq!(bus.retained("/test/game/state"), Some("lobby".to_string()));
+        assert_eq!(bus.retained("/test/game/placement_deadline"), None);
+        assert_eq!(bus.retained("/test/players/count"), Some("1".to_string()));
+
+        join(&bus, "bassie");
+        assert_eq!(bus.retained("/test/game/state"), Some("underway".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_turn_timeout_memory_bus() {
+        let mut bus = start(Config { turn_timeout: 0, max_timeouts: 1, ..config() });
+        let winner = collect(&mut bus, "/test/game/winner");
+        let first = current_player(&bus);
+
+        sleep(Duration::from_millis(100)).await;
+        let winner: Winner = deserialize(&winner.lock().unwrap()[0].1).unwrap();
+        assert_eq!(winner.team, other(&first));
+    }
+
+    #[tokio::test]
+    async fn test_reset_memory_bus() {
+        let bus = start(Config { cool_down: 0, ..config() });
+        play(&bus);
+
+        sleep(Duration::from_millis(100)).await;
+        assert_eq!(bus.retained("/test/game/state"), Some("lobby".to_string()));
+        assert_eq!(bus.retained("/test/players/count"), Some("0".to_string()));
+        assert_eq!(bus.retained("/test/game/stats/summary"), None);
+
+        join(&bus, "henkie");
+        join(&bus, "bassie");
+        assert_eq!(bus.retained("/test/game/state"), Some("underway".to_string()));
+    }
+
+    fn temporary_directory(name: &str) -> String {
+        let directory = std::env::temp_dir().join(format!("battleship-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        directory.to_string_lossy().into()
+    }
+
+    #[tokio::test]
+    async fn test_resume_memory_bus() {
+        let directory = temporary_directory("snapshots");
+        let config = Config { snapshots: Some(directory.clone()), resume: true, ..config() };
+        let bus = start(config.clone());
+        let first = current_player(&bus);
+        fire(&bus, &other(&first), 0, 0);
+
+        let mut resumed = MemoryBus::new();
+        let winner = collect(&mut resumed, "/test/game/winner");
+        host_games(config, "test", resumed.clone());
+        assert_eq!(resumed.retained("/test/game/state"), Some("underway".to_string()));
+        assert_eq!(current_player(&resumed), other(&first));
+        fire(&resumed, &first, 3, 3);
+        fire(&resumed, &other(&first), 1, 0);
+
+        let winner: Winner = deserialize(&winner.lock().unwrap()[0].1).unwrap();
+        assert_eq!(winner.team, first);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_journal_replay_memory_bus() {
+        let directory = temporary_directory("journal");
+        let bus = start(Config { journal: Some(directory.clone()), ..config() });
+        let winner = play(&bus);
+
+        let journal = fs::read_to_string(Path::new(&directory).join("test.jsonl")).unwrap();
+        let mut game = Game::new(Size::default(), "test".to_string(), Config::default());
+        for line in journal.lines() {
+            let entry: Entry = deserialize(&line.to_string()).unwrap();
+            entry.event.apply(&mut game);
+        }
+        assert_eq!(game.state, GameState::Over(winner.clone()));
+        assert_eq!(game.find_player(&winner).unwrap().1.shots().len(), 1);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    fn collect(bus: &mut MemoryBus, filter: &str) -> Arc<Mutex<Vec<(String, String)>>> {
+        let received = Arc::new(Mutex::new(vec![]));
+        let received_by_callback = received.clone();
+        bus.subscribe(filter, move |topic, payload| {
+            received_by_callback.lock().unwrap().push((topic.clone(), payload.clone()));
+        });
+        received
+    }
+
+    #[tokio::test]
+    async fn test_disclosure_memory_bus() {
+        for disclosure in &[Disclosure::Classic, Disclosure::Salvo, Disclosure::HiddenSink] {
+            let mut bus = MemoryBus::new();
+            let hits = collect(&mut bus, "/test/players/+/hit");
+            let shots = collect(&mut bus, "/test/players/+/shots");
+            let sunk = collect(&mut bus, "/test/players/#");
+            host_games(Config { disclosure: *disclosure, ..config() }, "test", bus.clone());
+            join(&bus, "henkie");
+            join(&bus, "bassie");
+
+            let first = current_player(&bus);
+            let second = if first == "henkie" { "bassie" } else { "henkie" }.to_string();
+            fire(&bus, &second, 0, 0);
+            fire(&bus, &first, 3, 3);
+            fire(&bus, &second, 1, 0);
+
+            let classic = *disclosure == Disclosure::Classic;
+            assert_eq!(hits.lock().unwrap().len(), if classic { 3 } else { 0 }, "{:?}", disclosure);
+            assert_eq!(shots.lock().unwrap().len(), if classic { 3 } else { 0 }, "{:?}", disclosure);
+            let sunk: Vec<String> = sunk.lock().unwrap().iter()
+                .map(|(topic, _)| topic.clone())
+                .filter(|topic| topic.ends_with("/sunk"))
+                .collect();
+            assert_eq!(sunk, vec![if *disclosure == Disclosure::HiddenSink {
+                format!("/test/players/{}/sunk", &second)
+            } else {
+                format!("/test/players/{}/ships/patrolboat/sunk", &second)
+            }], "{:?}", disclosure);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_single_team_does_not_start() {
+        let bus = start_with(config(), &[("henkie", Some("red")), ("bassie", Some("red"))]);
+        assert_eq!(bus.retained("/test/players/count"), Some("2".to_string()));
+        assert_eq!(bus.retained("/test/game/state"), None);
+        assert_eq!(bus.retained("/test/game/current"), None);
+    }
+
+    fn errors(bus: &mut MemoryBus) -> Arc<Mutex<Vec<(String, String)>>> {
+        collect(bus, "/test/players/+/error")
+    }
+
+    fn error(player: &str, error: GameError) -> (String, String) {
+        (format!("/test/players/{}/error", player), error.to_string())
+    }
+
+    #[tokio::test]
+    async fn test_repeated_shot_memory_bus() {
+        let mut bus = start(Config { reject_repeats: true, ..config() });
+        let errors = errors(&mut bus);
+        let first = current_player(&bus);
+        let second = other(&first);
+        fire(&bus, &second, 3, 3);
+        fire(&bus, &first, 3, 3);
+        fire(&bus, &second, 3, 3);
+        assert_eq!(
+            *errors.lock().unwrap(), vec![error(&first, GameError::RepeatedShot(second.clone(), Point::new(3, 3)))]
+        );
+        assert_eq!(bus.retained("/test/game/fired_shots"), Some("0".to_string()));
+        assert_eq!(current_player(&bus), first);
+
+        fire(&bus, &second, 2, 3);
+        assert_eq!(current_player(&bus), second);
+    }
+
+    #[tokio::test]
+    async fn test_salvo_size_memory_bus() {
+        let mut bus = start(config());
+        let errors = errors(&mut bus);
+        let first = current_player(&bus);
+        let second = other(&first);
+        let shot = |x, y| SalvoShot { target: second.clone(), x, y };
+        bus.publish(
+            format!("/test/players/{}/salvo", &first),
+            sign(vec![shot(0, 0), shot(1, 0)], format!("{}-secret", &first)).unwrap(),
+        );
+        assert_eq!(*errors.lock().unwrap(), vec![error(&first, GameError::WrongSalvoSize(1, 2))]);
+        assert_eq!(current_player(&bus), first);
+
+        bus.publish(
+            format!("/test/players/{}/salvo", &first),
+            sign(vec![shot(0, 0)], format!("{}-secret", &first)).unwrap(),
+        );
+        assert_eq!(errors.lock().unwrap().len(), 1);
+        assert_eq!(current_player(&bus), second);
+    }
+
+    const RED_AND_ADRIAAN: &[(&str, Option<&str>)] = &[
+        ("henkie", Some("red")), ("bassie", Some("red")), ("adriaan", None),
+    ];
+
+    /// The others fire at adriaan until the patrol boat of adriaan sinks, adriaan fires at the water.
+    fn sink_adriaan(bus: &MemoryBus) {
+        let mut cells = vec![(1, 0), (0, 0)];
+        while let Some((x, y)) = cells.last().cloned() {
+            if current_player(bus) == "adriaan" {
+                fire(bus, "henkie", 3, 3);
+            } else {
+                fire(bus, "adriaan", x, y);
+                cells.pop();
+            }
+        }
+    }
+
+    fn journal(directory: &str) -> Vec<Event> {
+        fs::read_to_string(Path::new(directory).join("test.jsonl")).unwrap().lines()
+            .map(|line| deserialize::<Entry>(&line.to_string()).unwrap().event)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_self_targeting_memory_bus() {
+        for policy in &[TargetPolicy::Reject, TargetPolicy::RejectAndConsume] {
+            let directory = temporary_directory(&format!("self-targeting-{:?}", policy));
+            let config = Config { self_targeting: *policy, journal: Some(directory.clone()), ..config() };
+            let mut bus = start(config);
+            let errors = errors(&mut bus);
+            let first = current_player(&bus);
+            fire(&bus, &first, 0, 0);
+            assert_eq!(*errors.lock().unwrap(), vec![error(&first, GameError::SelfTargeted)], "{:?}", policy);
+            if *policy == TargetPolicy::Reject {
+                assert_eq!(current_player(&bus), first);
+            } else {
+                assert_eq!(current_player(&bus), other(&first));
+            }
+            assert!(!journal(&directory).iter().any(|event| matches!(event, Event::Shot { .. })), "{:?}", policy);
+            fs::remove_dir_all(&directory).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_defeated_targets_memory_bus() {
+        for policy in &[TargetPolicy::Reject, TargetPolicy::RejectAndConsume] {
+            let players = [("henkie", None), ("bassie", None), ("adriaan", None)];
+            let mut bus = start_with(Config { defeated_targets: *policy, ..config() }, &players);
+            let errors = errors(&mut bus);
+            sink_adriaan(&bus);
+            assert_eq!(bus.retained("/test/game/state"), Some("underway".to_string()));
+
+            let shooter = current_player(&bus);
+            assert_ne!(shooter, "adriaan");
+            fire(&bus, "adriaan", 3, 3);
+            assert_eq!(
+                *errors.lock().unwrap(), vec![error(&shooter, GameError::TargetDefeated("adriaan".to_string()))],
+                "{:?}", policy
+            );
+            if *policy == TargetPolicy::Reject {
+                assert_eq!(current_player(&bus), shooter);
+            } else {
+                assert_eq!(current_player(&bus), other(&shooter));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_team_victory_memory_bus() {
+        let mut bus = start_with(Config { friendly_fire: FriendlyFire::Rejected, ..config() }, RED_AND_ADRIAAN);
+        let winner = collect(&mut bus, "/test/game/winner");
+        sink_adriaan(&bus);
+
+        let winner: Winner = deserialize(&winner.lock().unwrap()[0].1).unwrap();
+        assert_eq!(winner, Winner::new("red".to_string(), vec!["henkie".to_string(), "bassie".to_string()]));
+    }
+
+    #[tokio::test]
+    async fn test_ignored_friendly_fire_memory_bus() {
+        let directory = temporary_directory("friendly-fire");
+        let config = Config { friendly_fire: FriendlyFire::Ignored, journal: Some(directory.clone()), ..config() };
+        let bus = start_with(config, RED_AND_ADRIAAN);
+        if current_player(&bus) == "adriaan" {
+            fire(&bus, "henkie", 3, 3);
+        }
+
+        let shooter = current_player(&bus);
+        fire(&bus, &other(&shooter), 0, 0);
+        assert_ne!(current_player(&bus), shooter);
+        let fired = |event: &Event| matches!(event, Event::Shot { shooter: name, .. } if *name == shooter);
+        assert!(!journal(&directory).iter().any(fired));
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    fn join_protobuf(bus: &MemoryBus, name: &str) {
+        let player = Player::new(name.to_string(), format!("{}-secret", name), vec![], Size::default(), Adjacency::None);
+        bus.publish("/test/game/request", serialize_with(&player, Encoding::Protobuf).unwrap());
+        let ship = Ship::new(Point::new(0, 0), Orientation::Horizontal, Class::default());
+        bus.publish(
+            format!("/test/players/{}/ships/patrolboat/place", name),
+            serialize_with(&ship, Encoding::Protobuf).unwrap(),
+        );
+    }
+
+    fn decode<T: Message + Default>(payload: &String) -> T {
+        T::decode(base64::decode(payload).unwrap().as_slice()).unwrap()
+    }
+
+    fn current_player_protobuf(bus: &MemoryBus) -> String {
+        let current: protobuf::Turn = decode(&bus.retained("/test/game/current").expect("No current player"));
+        current.player
+    }
+
+    fn fire_protobuf(bus: &MemoryBus, target: &str, x: u8, y: u8) {
+        let shooter = current_player_protobuf(bus);
+        bus.publish(
+            format!("/test/players/{}/fire", target),
+            sign_with(Point::new(x, y), format!("{}-secret", shooter), Encoding::Protobuf).unwrap(),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_game_over_protobuf() {
+        let mut bus = MemoryBus::new();
+        let winner = Arc::new(Mutex::new(None));
+        let winner_for_callback = winner.clone();
+        bus.subscribe("/test/game/winner", move |_, payload| {
+            *winner_for_callback.lock().unwrap() = Some(payload.clone());
+        });
+
+        host_games(Config { encoding: Encoding::Protobuf, ..config() }, "test", bus.clone());
+        assert_eq!(bus.retained("/test/game/encoding"), Some("protobuf".to_string()));
+        join_protobuf(&bus, "henkie");
+        join_protobuf(&bus, "bassie");
+        assert_eq!(bus.retained("/test/game/state"), Some("underway".to_string()));
+
+        let first = current_player_protobuf(&bus);
+        let second = if first == "henkie" { "bassie" } else { "henkie" }.to_string();
+        fire_protobuf(&bus, &second, 0, 0);
+        assert_eq!(current_player_protobuf(&bus), second);
+        fire_protobuf(&bus, &first, 3, 3);
+        fire_protobuf(&bus, &second, 1, 0);
+
+        let winner: protobuf::Winner = decode(&winner.lock().unwrap().clone().expect("No winner"));
+        assert_eq!(winner.team, first);
+        assert_eq!(winner.members, vec![first]);
+    }
+}
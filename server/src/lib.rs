@@ -1,6 +1,7 @@
 
-mod messaging;
-mod model;
+pub mod engine;
+pub mod messaging;
+pub mod model;
 
 pub fn start_server() {
     // let mqtt = Mosquitto::new("localhost", 1883);
@@ -6,7 +6,7 @@ mod model;
 
 use crate::engine::start_engine;
 use simple_log::LogConfigBuilder;
-use model::size::Size;
+use model::config::Config;
 use std::env;
 use std::num::ParseIntError;
 use futures::future::err;
@@ -24,8 +24,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     if args.len() < 5 {
         error!("Please pass hostname, port, username and a game name as a command line argument!");
     } else {
+        let config = match Config::from_args(&args[5..]) {
+            Ok(config) => config,
+            Err(error) => {
+                error!("{}", error);
+                return Ok(());
+            }
+        };
         match args[2].parse() {
-            Ok(port) => start_engine(Size::default(), &args[4], &args[1], port, &args[3]).await,
+            Ok(port) => start_engine(config, &args[4], &args[1], port, &args[3]).await,
             Err(error) => error!("Unable to parse port: {:?}", error)
         }
     }
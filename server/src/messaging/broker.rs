@@ -0,0 +1,255 @@
+use crate::messaging::transport::matches;
+
+use bytes::{Bytes, BytesMut};
+use mqttbytes::{v4::*, QoS, Error};
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, tcp::OwnedReadHalf};
+use tokio::sync::mpsc::{self, UnboundedSender};
+use tokio::time::timeout;
+use log::{info, error};
+
+const MAX_PACKET_SIZE: usize = 1024 * 1024;
+
+struct Session {
+    sender: UnboundedSender<Packet>,
+    filters: Vec<String>,
+}
+
+#[derive(Default)]
+struct State {
+    next_id: usize,
+    sessions: BTreeMap<usize, Session>,
+    retained: BTreeMap<String, Bytes>,
+}
+
+/// A minimal in-process MQTT 3.1.1 broker, so a game can be played without installing one.
+///
+/// Messages are delivered to subscribers at QoS 0, which is what every subscription is granted.
+/// A QoS 2 message is held until its release, so a retransmission is delivered only once.
+/// Sessions are not persisted, every connection starts clean. Last wills are supported.
+#[derive(Clone)]
+pub struct Broker {
+    state: Arc<Mutex<State>>,
+}
+
+impl Broker {
+    /// Starts listening on the given port, connections are served in the background.
+    pub async fn start(port: u16) -> io::Result<Broker> {
+        let listener = TcpListener::bind(("0.0.0.0", port)).await?;
+        info!("Embedded broker listening on port {}", port);
+        let broker = Broker {
+            state: Arc::new(Mutex::new(State::default())),
+        };
+        let accepting = broker.clone();
+        tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, address)) => {
+                        info!("Broker accepted a connection from {}", address);
+                        let broker = accepting.clone();
+                        tokio::spawn(async move {
+                            if let Err(error) = broker.serve(stream).await {
+                                info!("Broker connection from {} closed: {}", address, error);
+                            }
+                        });
+                    }
+                    Err(error) => error!("Broker unable to accept a connection: {:?}", error),
+                }
+            }
+        });
+        Ok(broker)
+    }
+
+    async fn serve(&self, stream: TcpStream) -> Result<(), BrokerError> {
+        let (mut reader, mut writer) = stream.into_split();
+        let mut buffer = BytesMut::new();
+
+        let connect = match read_packet(&mut reader, &mut buffer, None).await? {
+            Packet::Connect(connect) => connect,
+            _ => return Err(BrokerError::Protocol),
+        };
+        let keep_alive = match connect.keep_alive {
+            0 => None,
+            seconds => Some(Duration::from_millis(seconds as u64 * 1500)),
+        };
+
+        let (sender, mut receiver) = mpsc::unbounded_channel::<Packet>();
+        let id = {
+            let mut state = self.state.lock().unwrap();
+            let id = state.next_id;
+            state.next_id += 1;
+            state.sessions.insert(id, Session { sender: sender.clone(), filters: vec![] });
+            id
+        };
+        sender.send(Packet::ConnAck(ConnAck::new(ConnectReturnCode::Success, false))).ok();
+
+        tokio::spawn(async move {
+            let mut output = BytesMut::new();
+            while let Some(packet) = receiver.recv().await {
+                if write_packet(&packet, &mut output).is_err() || writer.write_all(&output).await.is_err() {
+                    break;
+                }
+                output.clear();
+            }
+        });
+
+        let result = self.handle(id, &sender, &mut reader, &mut buffer, keep_alive).await;
+        self.state.lock().unwrap().sessions.remove(&id);
+        // The last will is only published when the client went away without disconnecting.
+        if let (Err(_), Some(will)) = (&result, connect.last_will) {
+            self.route(will.topic, will.message, will.retain);
+        }
+        result
+    }
+
+    async fn handle(
+        &self, id: usize, sender: &UnboundedSender<Packet>, reader: &mut OwnedReadHalf, buffer: &mut BytesMut,
+        keep_alive: Option<Duration>
+    ) -> Result<(), BrokerError> {
+        // QoS 2 messages by packet id, waiting for their PUBREL.
+        let mut unreleased: BTreeMap<u16, Publish> = BTreeMap::new();
+        loop {
+            match read_packet(reader, buffer, keep_alive).await? {
+                Packet::Publish(publish) => {
+                    if publish.topic.contains(|character| character == '+' || character == '#') {
+                        return Err(BrokerError::Protocol);
+                    }
+                    match publish.qos {
+                        QoS::AtMostOnce => self.route(publish.topic, publish.payload, publish.retain),
+                        QoS::AtLeastOnce => {
+                            sender.send(Packet::PubAck(PubAck::new(publish.pkid))).ok();
+                            self.route(publish.topic, publish.payload, publish.retain);
+                        }
+                        QoS::ExactlyOnce => {
+                            sender.send(Packet::PubRec(PubRec::new(publish.pkid))).ok();
+                            unreleased.insert(publish.pkid, publish);
+                        }
+                    }
+                }
+                Packet::PubRel(pubrel) => {
+                    if let Some(publish) = unreleased.remove(&pubrel.pkid) {
+                        self.route(publish.topic, publish.payload, publish.retain);
+                    }
+                    sender.send(Packet::PubComp(PubComp::new(pubrel.pkid))).ok();
+                }
+                Packet::Subscribe(subscribe) => self.subscribe(id, sender, subscribe),
+                Packet::Unsubscribe(unsubscribe) => {
+                    if let Some(session) = self.state.lock().unwrap().sessions.get_mut(&id) {
+                        session.filters.retain(|filter| !unsubscribe.topics.contains(filter));
+                    }
+                    sender.send(Packet::UnsubAck(UnsubAck::new(unsubscribe.pkid))).ok();
+                }
+                Packet::PingReq => { sender.send(Packet::PingResp).ok(); }
+                Packet::Disconnect => return Ok(()),
+                Packet::PubAck(_) | Packet::PubRec(_) | Packet::PubComp(_) => {}
+                _ => return Err(BrokerError::Protocol),
+            }
+        }
+    }
+
+    fn subscribe(&self, id: usize, sender: &UnboundedSender<Packet>, subscribe: Subscribe) {
+        let mut state = self.state.lock().unwrap();
+        let filters: Vec<String> = subscribe.filters.into_iter().map(|filter| filter.path).collect();
+        sender.send(Packet::SubAck(SubAck::new(
+            subscribe.pkid, filters.iter().map(|_| SubscribeReasonCode::Success(QoS::AtMostOnce)).collect()
+        ))).ok();
+        for (topic, payload) in state.retained.iter() {
+            if filters.iter().any(|filter| matches(filter, topic)) {
+                let mut publish = Publish::from_bytes(topic.clone(), QoS::AtMostOnce, payload.clone());
+                publish.retain = true;
+                sender.send(Packet::Publish(publish)).ok();
+            }
+        }
+        if let Some(session) = state.sessions.get_mut(&id) {
+            session.filters.extend(filters);
+        }
+    }
+
+    fn route(&self, topic: String, payload: Bytes, retain: bool) {
+        let mut state = self.state.lock().unwrap();
+        if retain {
+            if payload.is_empty() {
+                state.retained.remove(&topic);
+            } else {
+                state.retained.insert(topic.clone(), payload.clone());
+            }
+        }
+        for session in state.sessions.values() {
+            if session.filters.iter().any(|filter| matches(filter, &topic)) {
+                session.sender.send(Packet::Publish(Publish::from_bytes(topic.clone(), QoS::AtMostOnce, payload.clone()))).ok();
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+enum BrokerError {
+    IoError(io::Error),
+    MqttError(Error),
+    Protocol,
+    Closed,
+    KeepAliveExpired,
+}
+
+impl Display for BrokerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            BrokerError::IoError(error) => write!(f, "{}", error),
+            BrokerError::MqttError(error) => write!(f, "{}", error),
+            BrokerError::Protocol => write!(f, "protocol violation"),
+            BrokerError::Closed => write!(f, "connection closed"),
+            BrokerError::KeepAliveExpired => write!(f, "keep alive expired"),
+        }
+    }
+}
+
+impl From<io::Error> for BrokerError {
+    fn from(error: io::Error) -> Self {
+        BrokerError::IoError(error)
+    }
+}
+
+impl From<Error> for BrokerError {
+    fn from(error: Error) -> Self {
+        BrokerError::MqttError(error)
+    }
+}
+
+async fn read_packet(
+    reader: &mut OwnedReadHalf, buffer: &mut BytesMut, keep_alive: Option<Duration>
+) -> Result<Packet, BrokerError> {
+    loop {
+        match read(buffer, MAX_PACKET_SIZE) {
+            Ok(packet) => return Ok(packet),
+            Err(Error::InsufficientBytes(_)) => {}
+            Err(error) => return Err(error.into()),
+        }
+        let received = match keep_alive {
+            Some(keep_alive) => timeout(keep_alive, reader.read_buf(buffer)).await
+                .map_err(|_| BrokerError::KeepAliveExpired)??,
+            None => reader.read_buf(buffer).await?,
+        };
+        if received == 0 {
+            return Err(BrokerError::Closed);
+        }
+    }
+}
+
+fn write_packet(packet: &Packet, buffer: &mut BytesMut) -> Result<usize, Error> {
+    match packet {
+        Packet::ConnAck(connack) => connack.write(buffer),
+        Packet::Publish(publish) => publish.write(buffer),
+        Packet::PubAck(puback) => puback.write(buffer),
+        Packet::PubRec(pubrec) => pubrec.write(buffer),
+        Packet::PubComp(pubcomp) => pubcomp.write(buffer),
+        Packet::SubAck(suback) => suback.write(buffer),
+        Packet::UnsubAck(unsuback) => unsuback.write(buffer),
+        Packet::PingResp => PingResp.write(buffer),
+        _ => Err(Error::IncorrectPacketFormat),
+    }
+}
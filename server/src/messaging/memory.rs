@@ -0,0 +1,147 @@
+use crate::messaging::transport::{matches, Transport};
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+type Callback = Arc<Mutex<Box<dyn FnMut(&String, &String) + Send + 'static>>>;
+
+struct Subscription {
+    id: usize,
+    filter: String,
+    callback: Callback,
+}
+
+#[derive(Default)]
+struct Bus {
+    next_id: usize,
+    subscriptions: Vec<Subscription>,
+    retained: BTreeMap<String, String>,
+    /// Messages waiting to be delivered, to a single subscriber when it just subscribed to a retained topic.
+    queue: VecDeque<(String, String, Option<usize>)>,
+    dispatching: bool,
+}
+
+/// An in-process message bus with retained messages and wildcard subscriptions, for running games without a broker.
+///
+/// Messages are delivered on the thread that publishes them. Messages published from within a callback are
+/// queued and delivered after that callback returns, so callbacks never run re-entrantly.
+pub struct MemoryBus {
+    inner: Arc<Mutex<Bus>>,
+}
+
+impl Clone for MemoryBus {
+    fn clone(&self) -> Self {
+        MemoryBus {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl Default for MemoryBus {
+    fn default() -> Self {
+        MemoryBus::new()
+    }
+}
+
+impl MemoryBus {
+    pub fn new() -> MemoryBus {
+        MemoryBus {
+            inner: Arc::new(Mutex::new(Bus::default())),
+        }
+    }
+
+    pub fn retained(&self, topic: impl Into<String>) -> Option<String> {
+        self.inner.lock().unwrap().retained.get(&topic.into()).cloned()
+    }
+
+    fn send(&self, topic: String, payload: String, retain: bool) {
+        let mut bus = self.inner.lock().unwrap();
+        if retain {
+            if payload.is_empty() {
+                bus.retained.remove(&topic);
+            } else {
+                bus.retained.insert(topic.clone(), payload.clone());
+            }
+        }
+        bus.queue.push_back((topic, payload, None));
+        self.start_dispatching(bus);
+    }
+
+    fn start_dispatching(&self, mut bus: std::sync::MutexGuard<Bus>) {
+        if bus.dispatching {
+            return;
+        }
+        bus.dispatching = true;
+        drop(bus);
+        self.dispatch();
+    }
+
+    fn dispatch(&self) {
+        loop {
+            let (topic, payload, callbacks) = {
+                let mut bus = self.inner.lock().unwrap();
+                match bus.queue.pop_front() {
+                    Some((topic, payload, subscriber)) => {
+                        let callbacks: Vec<Callback> = bus.subscriptions.iter()
+                            .filter(|subscription| subscriber.map_or(true, |id| id == subscription.id))
+                            .filter(|subscription| matches(&subscription.filter, &topic))
+                            .map(|subscription| Arc::clone(&subscription.callback))
+                            .collect();
+                        (topic, payload, callbacks)
+                    }
+                    None => {
+                        bus.dispatching = false;
+                        return;
+                    }
+                }
+            };
+            for callback in callbacks {
+                (callback.lock().unwrap())(&topic, &payload);
+            }
+        }
+    }
+}
+
+impl Transport for MemoryBus {
+    fn subscribe(&mut self, topic: impl Into<String>, callback: impl FnMut(&String, &String) + Send + 'static) {
+        let filter = topic.into();
+        let mut bus = self.inner.lock().unwrap();
+        let id = bus.next_id;
+        bus.next_id += 1;
+        let retained: Vec<(String, String)> = bus.retained.iter()
+            .filter(|(topic, _)| matches(&filter, topic))
+            .map(|(topic, payload)| (topic.clone(), payload.clone()))
+            .collect();
+        for (topic, payload) in retained {
+            bus.queue.push_back((topic, payload, Some(id)));
+        }
+        bus.subscriptions.push(Subscription {
+            id, filter, callback: Arc::new(Mutex::new(Box::new(callback))),
+        });
+        self.start_dispatching(bus);
+    }
+
+    fn unsubscribe(&mut self, topic: impl Into<String>) {
+        let filter = topic.into();
+        self.inner.lock().unwrap().subscriptions.retain(|subscription| subscription.filter != filter);
+    }
+
+    fn publish(&self, topic: impl Into<String>, payload: impl Into<String>) {
+        self.send(topic.into(), payload.into(), false);
+    }
+
+    fn retain(&self, topic: impl Into<String>, payload: impl Into<String>) {
+        self.send(topic.into(), payload.into(), true);
+    }
+
+    fn clear(&self, topic: impl Into<String>) {
+        self.send(topic.into(), String::new(), true);
+    }
+
+    fn retained_matching(&self, filter: &str) -> Vec<(String, String)> {
+        self.inner.lock().unwrap().retained.iter()
+            .filter(|(topic, _)| matches(filter, topic))
+            .map(|(topic, payload)| (topic.clone(), payload.clone()))
+            .collect()
+    }
+}
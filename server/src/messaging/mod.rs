@@ -1,3 +1,8 @@
+pub mod broker;
+pub mod protobuf;
 pub mod translate;
 pub mod mqtt;
+pub mod memory;
+pub mod transport;
+pub mod websocket;
 mod tests;
@@ -1,13 +1,17 @@
-use crate::messaging::mqtt::Request::{Subscription, Publication};
+use crate::messaging::mqtt::Request::{Subscription, Publication, Connected};
+use crate::messaging::transport::{matches, Transport};
 
-use std::collections::HashMap;
+use std::cmp::min;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::future::Future;
+use std::time::Duration;
 use std::sync::{
     Arc, Mutex, mpsc::{self, Sender, Receiver}
 };
 
-use rumqttc::{self, AsyncClient, Event, MqttOptions, Packet, QoS};
+use rumqttc::{self, AsyncClient, Event, LastWill, MqttOptions, Packet, QoS};
 use tokio::task;
+use tokio::time::sleep;
 use tokio::task::JoinHandle;
 use futures::future::{join3, Join3};
 use delegate::delegate;
@@ -18,11 +22,19 @@ pub enum Request {
     Subscription(String, Box<dyn FnMut(&String, &String) + Send + 'static>),
     Unsubscription(String),
     Publication(String, String, bool),
+    /// The connection to the broker was established, or re-established after it was lost.
+    Connected,
 }
 
+const MIN_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// Publications held back while disconnected, beyond this the oldest are dropped.
+const MAX_BUFFERED: usize = 1000;
+
 pub struct Mosquitto {
     running: Arc<Mutex<bool>>,
     sender: Sender<Request>,
+    retained: Mutex<BTreeMap<String, String>>,
 }
 
 pub struct MosquittoArc {
@@ -52,24 +64,49 @@ impl MosquittoArc {
 
     delegate! {
         to self.inner.lock().unwrap() {
-            pub fn subscribe(&mut self, topic: impl Into<String>, callback: impl FnMut(&String, &String) + Send + 'static);
-            pub fn unsubscribe(&mut self, topic: impl Into<String>);
-            pub fn publish(&self, topic: impl Into<String>, payload: impl Into<String>);
-            pub fn retain(&self, topic: impl Into<String>, payload: impl Into<String>);
-            pub fn clear(&self, topic: impl Into<String>);
             pub fn stop(&mut self);
         }
     }
 }
 
+impl Transport for MosquittoArc {
+    delegate! {
+        to self.inner.lock().unwrap() {
+            fn subscribe(&mut self, topic: impl Into<String>, callback: impl FnMut(&String, &String) + Send + 'static);
+            fn unsubscribe(&mut self, topic: impl Into<String>);
+            fn publish(&self, topic: impl Into<String>, payload: impl Into<String>);
+            fn retain(&self, topic: impl Into<String>, payload: impl Into<String>);
+            fn clear(&self, topic: impl Into<String>);
+            fn retained_matching(&self, filter: &str) -> Vec<(String, String)>;
+        }
+    }
+}
+
+/// Subscribes to the topic, retrying with backoff as long as the client refuses the request.
+async fn subscribe_with_retry(client: &AsyncClient, topic: &str, running: &Mutex<bool>) {
+    let mut backoff = MIN_BACKOFF;
+    while let Err(error) = client.subscribe(topic, QoS::ExactlyOnce).await {
+        if !*running.lock().unwrap() {
+            return;
+        }
+        error!("Unable to subscribe to topic: {}, retrying in {:?}: {:?}", topic, backoff, error);
+        sleep(backoff).await;
+        backoff = min(backoff * 2, MAX_BACKOFF);
+    }
+}
+
 impl Mosquitto {
     pub fn new(
         id: impl Into<String>, host: impl Into<String>, port: u16, user: impl Into<String>
     ) -> (Mosquitto, Join3<JoinHandle<()>, impl Future<Output=()>, JoinHandle<()>>) {
+        let id = id.into();
         let user = user.into();
+        // Retained "online" while connected, the broker publishes "offline" when the connection is lost.
+        let connection_topic = format!("/connections/{}", &id);
         let mut mqttoptions = MqttOptions::new(id, host.into(), port);
         mqttoptions.set_credentials(user, String::from(""));
         mqttoptions.set_keep_alive(5);
+        mqttoptions.set_last_will(LastWill::new(&connection_topic, "offline", QoS::AtLeastOnce, true));
 
         let (client, mut eventloop) = AsyncClient::new(mqttoptions, 10);
         let callbacks = Arc::new(Mutex::new(HashMap::<String, Vec<Box<dyn FnMut(&String, &String) + Send + 'static>>>::new()));
@@ -82,50 +119,105 @@ impl Mosquitto {
         let running_for_eventloop = Arc::clone(&running);
         let running_for_poller1 = Arc::clone(&running);
         let running_for_poller2 = Arc::clone(&running);
+        let running_for_subscriptions = Arc::clone(&running);
+        let connected = Arc::new(Mutex::from(false));
+        let connected_for_eventloop = Arc::clone(&connected);
+        let connected_for_poller = Arc::clone(&connected);
 
         let (subscription_sender, subscription_receiver): (Sender<Request>, Receiver<Request>) = mpsc::channel();
+        let connection_sender = subscription_sender.clone();
 
         (
             Mosquitto {
                 running: running,
                 sender: subscription_sender,
+                retained: Mutex::new(BTreeMap::new()),
             },
             join3(
                 task::spawn(async move {
+                    let mut backoff = MIN_BACKOFF;
                     while *running_for_eventloop.lock().unwrap() {
-                        if let Ok(event) = eventloop.poll().await {
-                            if let Event::Incoming(incoming) = event {
-                                if let Packet::Publish(publish) = incoming {
-                                    if let Ok(payload) = String::from_utf8(publish.payload.to_vec()) {
-                                        event_sender.send((publish.topic.clone(), payload.clone())).unwrap();
+                        match eventloop.poll().await {
+                            Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                                info!("Connected to the broker");
+                                backoff = MIN_BACKOFF;
+                                connection_sender.send(Connected).ok();
+                            }
+                            Ok(Event::Incoming(Packet::Publish(publish))) => {
+                                if let Ok(payload) = String::from_utf8(publish.payload.to_vec()) {
+                                    event_sender.send((publish.topic.clone(), payload.clone())).unwrap();
+                                }
+                            }
+                            Ok(_) => {}
+                            Err(error) => {
+                                {
+                                    let mut connected = connected_for_eventloop.lock().unwrap();
+                                    if *connected {
+                                        error!("Lost the connection to the broker: {:?}", error);
+                                    } else {
+                                        error!("Unable to connect to the broker, retrying in {:?}: {:?}", backoff, error);
                                     }
+                                    *connected = false;
                                 }
+                                sleep(backoff).await;
+                                backoff = min(backoff * 2, MAX_BACKOFF);
                             }
                         }
                     }
                 }),
                 async move {
+                    // Publications made while disconnected, sent once the connection is back.
+                    let mut buffered = VecDeque::new();
                     while *running_for_poller1.lock().unwrap() {
                         if let Ok(request) = subscription_receiver.recv() {
+                            let connected = *connected_for_poller.lock().unwrap();
                             match request {
                                 Subscription(topic, callback) => {
                                     info!("Subscribing to topic: {}", &topic);
-                                    let mut callbacks = callbacks_for_poller.lock().unwrap();
-                                    match callbacks.get_mut(&topic) {
-                                        Some(callbacks) => callbacks.push(callback),
-                                        None => {
-                                            callbacks.insert(topic.clone(), vec![callback]);
-                                        },
+                                    {
+                                        let mut callbacks = callbacks_for_poller.lock().unwrap();
+                                        match callbacks.get_mut(&topic) {
+                                            Some(callbacks) => callbacks.push(callback),
+                                            None => {
+                                                callbacks.insert(topic.clone(), vec![callback]);
+                                            },
+                                        }
+                                    }
+                                    if connected {
+                                        subscribe_with_retry(&client, &topic, &running_for_subscriptions).await;
                                     }
-                                    client.subscribe(&topic, QoS::ExactlyOnce).await.unwrap();
                                 }
-                                Publication(topic, payload, retain) => {
+                                Publication(topic, payload, retain) if connected => {
                                     info!("Publishing to topic: {}, the following: {}", topic, payload);
                                     client.publish(topic, QoS::ExactlyOnce, retain, payload).await.unwrap();
                                 }
+                                Publication(topic, payload, retain) => {
+                                    info!("Not connected, holding back publication to topic: {}", topic);
+                                    if buffered.len() == MAX_BUFFERED {
+                                        if let Some((dropped, _, _)) = buffered.pop_front() {
+                                            error!("Holding back too many publications, dropping: {}", dropped);
+                                        }
+                                    }
+                                    buffered.push_back((topic, payload, retain));
+                                }
                                 Unsubscription(topic) => {
                                     callbacks_for_poller.lock().unwrap().remove(&topic);
-                                    client.unsubscribe(topic).await.unwrap();
+                                    if connected {
+                                        client.unsubscribe(topic).await.unwrap();
+                                    }
+                                }
+                                Connected => {
+                                    *connected_for_poller.lock().unwrap() = true;
+                                    client.publish(&connection_topic, QoS::AtLeastOnce, true, "online").await.unwrap();
+                                    let topics: Vec<String> = callbacks_for_poller.lock().unwrap().keys().cloned().collect();
+                                    for topic in topics {
+                                        info!("Subscribing to topic: {}", &topic);
+                                        subscribe_with_retry(&client, &topic, &running_for_subscriptions).await;
+                                    }
+                                    for (topic, payload, retain) in buffered.drain(..) {
+                                        info!("Publishing to topic: {}, the following: {}", topic, payload);
+                                        client.publish(topic, QoS::ExactlyOnce, retain, payload).await.unwrap();
+                                    }
                                 }
                             }
                         }
@@ -135,9 +227,11 @@ impl Mosquitto {
                     while *running_for_poller2.lock().unwrap() {
                         if let Ok((topic, payload)) = event_receiver.recv() {
                             info!("Got a event!");
-                            if let Some(callbacks) = callbacks_for_thread.lock().unwrap().get_mut(&topic) {
-                                for callback in callbacks {
-                                    callback(&topic, &payload);
+                            for (filter, callbacks) in callbacks_for_thread.lock().unwrap().iter_mut() {
+                                if matches(filter, &topic) {
+                                    for callback in callbacks {
+                                        callback(&topic, &payload);
+                                    }
                                 }
                             }
                         }
@@ -160,11 +254,27 @@ impl Mosquitto {
     }
 
     pub fn retain(&self, topic: impl Into<String>, payload: impl Into<String>) {
-        self.sender.send(Publication(topic.into(), payload.into(), true)).unwrap();
+        let (topic, payload) = (topic.into(), payload.into());
+        if payload.is_empty() {
+            self.retained.lock().unwrap().remove(&topic);
+        } else {
+            self.retained.lock().unwrap().insert(topic.clone(), payload.clone());
+        }
+        self.sender.send(Publication(topic, payload, true)).unwrap();
     }
 
     pub fn clear(&self, topic: impl Into<String>) {
-        self.sender.send(Publication(topic.into(), String::new(), true)).unwrap();
+        let topic = topic.into();
+        self.retained.lock().unwrap().remove(&topic);
+        self.sender.send(Publication(topic, String::new(), true)).unwrap();
+    }
+
+    /// Only knows about messages retained through this connection, not those retained by other clients.
+    pub fn retained_matching(&self, filter: &str) -> Vec<(String, String)> {
+        self.retained.lock().unwrap().iter()
+            .filter(|(topic, _)| matches(filter, topic))
+            .map(|(topic, payload)| (topic.clone(), payload.clone()))
+            .collect()
     }
 
     pub fn stop(&mut self) {
@@ -0,0 +1,280 @@
+use crate::messaging::translate::{FromMessage, IntoMessage, Result, TranslationError};
+use crate::model::{
+    self,
+    adjacency, class::Class, disclosure, friendlyfire, orientation, salvo, salvosize, stats, targetpolicy,
+};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+include!(concat!(env!("OUT_DIR"), "/battleship.rs"));
+
+fn narrow(value: u32, field: &str) -> Result<u8> {
+    u8::try_from(value).map_err(|_| TranslationError::InvalidMessage(format!("{} out of range: {}", field, value)))
+}
+
+impl IntoMessage for model::point::Point {
+    type Message = Point;
+
+    fn to_message(&self) -> Point {
+        Point { x: self.x as u32, y: self.y as u32 }
+    }
+}
+
+impl FromMessage for model::point::Point {
+    type Message = Point;
+
+    fn from_message(message: Point) -> Result<Self> {
+        Ok(model::point::Point::new(narrow(message.x, "x")?, narrow(message.y, "y")?))
+    }
+}
+
+impl IntoMessage for model::size::Size {
+    type Message = Size;
+
+    fn to_message(&self) -> Size {
+        Size { width: self.width as u32, height: self.height as u32 }
+    }
+}
+
+impl IntoMessage for model::player::Player {
+    type Message = Registration;
+
+    fn to_message(&self) -> Registration {
+        Registration {
+            name: self.name.clone(),
+            secret: self.secret.clone(),
+            team: self.team.clone().unwrap_or_default(),
+        }
+    }
+}
+
+impl FromMessage for model::player::Player {
+    type Message = Registration;
+
+    fn from_message(message: Registration) -> Result<Self> {
+        let team = Some(message.team).filter(|team| !team.is_empty());
+        Ok(model::player::Player::new(
+            message.name, message.secret, vec![], model::size::Size::default(), adjacency::Adjacency::None
+        ).in_team(team))
+    }
+}
+
+impl IntoMessage for model::ship::Ship {
+    type Message = Placement;
+
+    fn to_message(&self) -> Placement {
+        Placement {
+            coordinates: Some(self.coordinates.to_message()),
+            orientation: match self.orientation {
+                orientation::Orientation::Horizontal => Orientation::Horizontal,
+                orientation::Orientation::Vertical => Orientation::Vertical,
+            } as i32,
+            rotation: self.rotation as u32,
+            mirrored: self.mirrored,
+        }
+    }
+}
+
+impl FromMessage for model::ship::Ship {
+    type Message = Placement;
+
+    /// The class is not part of the placement, it follows from the topic.
+    fn from_message(message: Placement) -> Result<Self> {
+        let coordinates = message.coordinates
+            .ok_or_else(|| TranslationError::InvalidMessage(String::from("Placement without coordinates")))?;
+        let orientation = match Orientation::from_i32(message.orientation) {
+            Some(Orientation::Horizontal) => orientation::Orientation::Horizontal,
+            Some(Orientation::Vertical) => orientation::Orientation::Vertical,
+            None => return Err(TranslationError::InvalidMessage(format!("Unknown orientation: {}", message.orientation))),
+        };
+        if message.rotation >= 4 {
+            return Err(TranslationError::InvalidMessage(format!("rotation out of range: {}", message.rotation)));
+        }
+        Ok(model::ship::Ship::new(model::point::Point::from_message(coordinates)?, orientation, Class::default())
+            .rotated(message.rotation as u8, message.mirrored))
+    }
+}
+
+impl IntoMessage for Vec<salvo::SalvoShot> {
+    type Message = Salvo;
+
+    fn to_message(&self) -> Salvo {
+        Salvo {
+            shots: self.iter()
+                .map(|shot| SalvoShot { target: shot.target.clone(), x: shot.x as u32, y: shot.y as u32 })
+                .collect(),
+        }
+    }
+}
+
+impl FromMessage for Vec<salvo::SalvoShot> {
+    type Message = Salvo;
+
+    fn from_message(message: Salvo) -> Result<Self> {
+        message.shots.into_iter()
+            .map(|shot| Ok(salvo::SalvoShot { target: shot.target, x: narrow(shot.x, "x")?, y: narrow(shot.y, "y")? }))
+            .collect()
+    }
+}
+
+impl IntoMessage for Vec<Class> {
+    type Message = Fleet;
+
+    fn to_message(&self) -> Fleet {
+        Fleet {
+            classes: self.iter()
+                .map(|class| ShipClass {
+                    name: class.name(),
+                    length: class.length as u32,
+                    token: class.token.clone(),
+                    shape: class.shape.iter().map(|point| point.to_message()).collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl IntoMessage for model::rules::Rules {
+    type Message = Rules;
+
+    fn to_message(&self) -> Rules {
+        Rules {
+            adjacency: match self.adjacency {
+                adjacency::Adjacency::None => Adjacency::None,
+                adjacency::Adjacency::Orthogonal => Adjacency::Orthogonal,
+                adjacency::Adjacency::Diagonal => Adjacency::Diagonal,
+            } as i32,
+            disclosure: match self.disclosure {
+                disclosure::Disclosure::Classic => Disclosure::Classic,
+                disclosure::Disclosure::Salvo => Disclosure::Salvo,
+                disclosure::Disclosure::HiddenSink => Disclosure::HiddenSink,
+            } as i32,
+            salvo_size: match self.salvo_size {
+                salvosize::SalvoSize::Ships => SalvoSize::Ships,
+                salvosize::SalvoSize::Fixed(_) => SalvoSize::Fixed,
+                salvosize::SalvoSize::Cells => SalvoSize::Cells,
+                salvosize::SalvoSize::Largest => SalvoSize::Largest,
+            } as i32,
+            fixed_salvo_size: match self.salvo_size {
+                salvosize::SalvoSize::Fixed(shots) => shots as u32,
+                _ => 0,
+            },
+            friendly_fire: match self.friendly_fire {
+                friendlyfire::FriendlyFire::Allowed => FriendlyFire::Allowed,
+                friendlyfire::FriendlyFire::Rejected => FriendlyFire::Rejected,
+                friendlyfire::FriendlyFire::Ignored => FriendlyFire::Ignored,
+            } as i32,
+            reject_repeats: self.reject_repeats,
+            self_targeting: target_policy(self.self_targeting) as i32,
+            defeated_targets: target_policy(self.defeated_targets) as i32,
+        }
+    }
+}
+
+fn target_policy(policy: targetpolicy::TargetPolicy) -> TargetPolicy {
+    match policy {
+        targetpolicy::TargetPolicy::Allow => TargetPolicy::Allow,
+        targetpolicy::TargetPolicy::Reject => TargetPolicy::Reject,
+        targetpolicy::TargetPolicy::RejectAndConsume => TargetPolicy::RejectAndConsume,
+    }
+}
+
+impl IntoMessage for Box<[String]> {
+    type Message = Players;
+
+    fn to_message(&self) -> Players {
+        Players { names: self.to_vec() }
+    }
+}
+
+impl IntoMessage for BTreeMap<String, Vec<String>> {
+    type Message = Teams;
+
+    fn to_message(&self) -> Teams {
+        Teams {
+            teams: self.iter().map(|(team, members)| (team.clone(), Players { names: members.clone() })).collect(),
+        }
+    }
+}
+
+impl IntoMessage for model::turn::Turn {
+    type Message = Turn;
+
+    fn to_message(&self) -> Turn {
+        Turn { player: self.player.clone(), deadline: self.deadline, shots: self.shots as u32 }
+    }
+}
+
+impl IntoMessage for model::shotresult::ShotResult {
+    type Message = ShotResult;
+
+    fn to_message(&self) -> ShotResult {
+        ShotResult {
+            target: self.target.clone(),
+            coordinates: Some(self.coordinates.to_message()),
+            hit: self.hit,
+        }
+    }
+}
+
+impl IntoMessage for salvo::SalvoResult {
+    type Message = SalvoResult;
+
+    fn to_message(&self) -> SalvoResult {
+        SalvoResult {
+            shots: self.shots as u32,
+            hits: self.hits as u32,
+            results: self.results.iter().map(|result| result.to_message()).collect(),
+        }
+    }
+}
+
+impl IntoMessage for BTreeMap<String, u8> {
+    type Message = Hits;
+
+    fn to_message(&self) -> Hits {
+        Hits {
+            targets: self.iter().map(|(target, hits)| (target.clone(), *hits as u32)).collect(),
+        }
+    }
+}
+
+impl IntoMessage for model::winner::Winner {
+    type Message = Winner;
+
+    fn to_message(&self) -> Winner {
+        Winner { team: self.team.clone(), members: self.members.clone() }
+    }
+}
+
+impl IntoMessage for stats::PlayerStats {
+    type Message = PlayerStats;
+
+    fn to_message(&self) -> PlayerStats {
+        PlayerStats {
+            shots: self.shots,
+            hits: self.hits,
+            accuracy: self.accuracy,
+            ships_sunk: self.ships_sunk,
+            turns_survived: self.turns_survived,
+            elimination: self.elimination.map_or(0, |elimination| elimination as u32),
+            turn_times: self.turn_times.clone(),
+            repeated_shots: self.repeated_shots,
+        }
+    }
+}
+
+impl IntoMessage for stats::Summary {
+    type Message = Summary;
+
+    fn to_message(&self) -> Summary {
+        Summary {
+            winner: self.winner.clone().unwrap_or_default(),
+            turns: self.turns,
+            shots: self.shots,
+            hits: self.hits,
+            accuracy: self.accuracy,
+            eliminations: self.eliminations.clone(),
+        }
+    }
+}
@@ -3,10 +3,28 @@
 mod tests {
     use serde::{Deserialize, Serialize};
     use crate::messaging::translate::{
-        serialize, encrypt, decrypt, verify, sign, TranslationError, Result
+        serialize, encrypt, decrypt, verify, sign, TranslationError, Result,
+        serialize_with, deserialize_with, sign_with, verify_with,
     };
+    use crate::model::{encoding::Encoding, point::Point, salvo::SalvoShot, ship::Ship, turn::Turn, class::Class};
+    use crate::model::orientation::Orientation::Vertical;
+    use crate::messaging::broker::Broker;
+    use crate::messaging::memory::MemoryBus;
+    use crate::messaging::mqtt::MosquittoArc;
+    use crate::messaging::transport::{matches, Transport};
+    use crate::messaging::websocket::{accept_key, Gateway, Request, Response};
+    use crate::messaging::translate::deserialize;
+    use bytes::BytesMut;
     use lazy_static::lazy_static;
+    use mqttbytes::v4;
+    use log::info;
     use std::ops::Deref;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use rumqttc::{AsyncClient, EventLoop, MqttOptions, QoS, Event, Packet};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+    use tokio::time::{sleep, timeout};
 
     #[derive(Serialize, Deserialize, Debug)]
     pub struct Test {
@@ -68,4 +86,258 @@ mod tests {
             }
         });
     }
+
+    #[test]
+    fn test_topic_filters() {
+        assert!(matches("/game/state", "/game/state"));
+        assert!(matches("/players/+/fire", "/players/henkie/fire"));
+        assert!(!matches("/players/+/fire", "/players/henkie/ships/fire"));
+        assert!(matches("/players/#", "/players/henkie/ships/carrier/place"));
+        assert!(matches("/players/#", "/players"));
+        assert!(!matches("/game/state", "/game/state/extra"));
+    }
+
+    #[test]
+    fn test_memory_bus() {
+        let mut bus = MemoryBus::new();
+        let received = Arc::new(Mutex::new(vec![]));
+        bus.retain("/game/state", "lobby");
+        bus.retain("/game/size", "{}");
+        bus.clear("/game/size");
+
+        let received_by_callback = received.clone();
+        bus.subscribe("/game/+", move |topic, payload| {
+            received_by_callback.lock().unwrap().push((topic.clone(), payload.clone()))
+        });
+        bus.publish("/game/hits", "2");
+        bus.publish("/players/count", "3");
+        bus.unsubscribe("/game/+");
+        bus.publish("/game/hits", "4");
+
+        assert_eq!(bus.retained("/game/state"), Some("lobby".to_string()));
+        assert_eq!(bus.retained("/game/size"), None);
+        assert_eq!(*received.lock().unwrap(), vec![
+            ("/game/state".to_string(), "lobby".to_string()),
+            ("/game/hits".to_string(), "2".to_string()),
+        ]);
+    }
+
+    async fn next_publication(eventloop: &mut EventLoop) -> (String, String, bool) {
+        loop {
+            if let Event::Incoming(Packet::Publish(publish)) = eventloop.poll().await.expect("Connection lost") {
+                return (publish.topic, String::from_utf8(publish.payload.to_vec()).unwrap(), publish.retain);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_embedded_broker() {
+        Broker::start(18830).await.expect("Unable to start the broker");
+
+        let (publisher, mut publisher_loop) = AsyncClient::new(MqttOptions::new("publisher", "localhost", 18830), 10);
+        tokio::spawn(async move {
+            while publisher_loop.poll().await.is_ok() {}
+        });
+        publisher.publish("/game/state", QoS::ExactlyOnce, true, "lobby").await.unwrap();
+        sleep(Duration::from_millis(200)).await;
+
+        let (subscriber, mut subscriber_loop) = AsyncClient::new(MqttOptions::new("subscriber", "localhost", 18830), 10);
+        subscriber.subscribe("/game/#", QoS::ExactlyOnce).await.unwrap();
+        let retained = timeout(Duration::from_secs(5), next_publication(&mut subscriber_loop)).await.unwrap();
+        assert_eq!(retained, ("/game/state".to_string(), "lobby".to_string(), true));
+
+        publisher.publish("/game/hits", QoS::AtLeastOnce, false, "2").await.unwrap();
+        let published = timeout(Duration::from_secs(5), next_publication(&mut subscriber_loop)).await.unwrap();
+        assert_eq!(published, ("/game/hits".to_string(), "2".to_string(), false));
+    }
+
+    #[tokio::test]
+    async fn test_embedded_broker_exactly_once() {
+        Broker::start(18833).await.expect("Unable to start the broker");
+        let (subscriber, mut subscriber_loop) = AsyncClient::new(MqttOptions::new("subscriber", "localhost", 18833), 10);
+        subscriber.subscribe("/game/#", QoS::AtMostOnce).await.unwrap();
+        while !matches!(subscriber_loop.poll().await.unwrap(), Event::Incoming(Packet::SubAck(_))) {}
+
+        // A QoS 2 message, its retransmission and its release, followed by a QoS 0 message.
+        let mut packets = BytesMut::new();
+        v4::Connect::new("raw").write(&mut packets).unwrap();
+        let mut publish = v4::Publish::new("/game/hits", mqttbytes::QoS::ExactlyOnce, "2");
+        publish.pkid = 1;
+        publish.write(&mut packets).unwrap();
+        publish.dup = true;
+        publish.write(&mut packets).unwrap();
+        v4::PubRel::new(1).write(&mut packets).unwrap();
+        v4::Publish::new("/game/state", mqttbytes::QoS::AtMostOnce, "over").write(&mut packets).unwrap();
+        let mut stream = TcpStream::connect("localhost:18833").await.unwrap();
+        stream.write_all(&packets).await.unwrap();
+
+        let published = timeout(Duration::from_secs(5), next_publication(&mut subscriber_loop)).await.unwrap();
+        assert_eq!(published, ("/game/hits".to_string(), "2".to_string(), false));
+        let published = timeout(Duration::from_secs(5), next_publication(&mut subscriber_loop)).await.unwrap();
+        assert_eq!(published, ("/game/state".to_string(), "over".to_string(), false));
+
+        // Publishing on a topic filter is a protocol violation, the broker closes the connection.
+        let mut packets = BytesMut::new();
+        v4::Publish::new("/game/+", mqttbytes::QoS::AtMostOnce, "2").write(&mut packets).unwrap();
+        stream.write_all(&packets).await.unwrap();
+        let mut received = vec![];
+        timeout(Duration::from_secs(5), stream.read_to_end(&mut received)).await.unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_websocket_accept_key() {
+        assert_eq!(accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+    }
+
+    async fn send_frame(stream: &mut TcpStream, request: &Request) {
+        let payload = serialize(request).unwrap().into_bytes();
+        let mask = [1u8, 2, 3, 4];
+        let mut frame = vec![0x81, 0x80 | payload.len() as u8];
+        frame.extend_from_slice(&mask);
+        frame.extend(payload.iter().enumerate().map(|(index, byte)| byte ^ mask[index % 4]));
+        stream.write_all(&frame).await.unwrap();
+    }
+
+    async fn next_response(stream: &mut TcpStream) -> Response {
+        let mut header = [0u8; 2];
+        stream.read_exact(&mut header).await.unwrap();
+        let length = match header[1] {
+            126 => stream.read_u16().await.unwrap() as usize,
+            length => length as usize,
+        };
+        let mut payload = vec![0u8; length];
+        stream.read_exact(&mut payload).await.unwrap();
+        deserialize(&String::from_utf8(payload).unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_websocket_gateway() {
+        let mut bus = MemoryBus::new();
+        bus.retain("/game/state", "lobby");
+        let received = Arc::new(Mutex::new(vec![]));
+        let received_by_callback = received.clone();
+        bus.subscribe("/players/+/fire", move |topic, payload| {
+            received_by_callback.lock().unwrap().push((topic.clone(), payload.clone()))
+        });
+        Gateway::start(18831, bus.clone()).await.expect("Unable to start the gateway");
+
+        let mut stream = TcpStream::connect("localhost:18831").await.unwrap();
+        stream.write_all(
+            b"GET / HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n"
+        ).await.unwrap();
+        let mut handshake = vec![];
+        while !handshake.ends_with(b"\r\n\r\n") {
+            handshake.push(stream.read_u8().await.unwrap());
+        }
+        let handshake = String::from_utf8(handshake).unwrap();
+        assert!(handshake.starts_with("HTTP/1.1 101"));
+        assert!(handshake.contains("Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo="));
+
+        send_frame(&mut stream, &Request::Subscribe { topic: "/game/#".to_string() }).await;
+        let retained = timeout(Duration::from_secs(5), next_response(&mut stream)).await.unwrap();
+        assert_eq!(retained, Response::Message {
+            topic: "/game/state".to_string(), payload: "lobby".to_string(), retained: true,
+        });
+
+        bus.publish("/game/hits", "2");
+        let published = timeout(Duration::from_secs(5), next_response(&mut stream)).await.unwrap();
+        assert_eq!(published, Response::Message {
+            topic: "/game/hits".to_string(), payload: "2".to_string(), retained: false,
+        });
+
+        send_frame(&mut stream, &Request::Publish {
+            topic: "/players/bob/fire".to_string(), payload: "{}".to_string(), retain: false,
+        }).await;
+        send_frame(&mut stream, &Request::Publish {
+            topic: "/game/state".to_string(), payload: "underway".to_string(), retain: true,
+        }).await;
+        let echoed = timeout(Duration::from_secs(5), next_response(&mut stream)).await.unwrap();
+        assert_eq!(echoed, Response::Message {
+            topic: "/game/state".to_string(), payload: "underway".to_string(), retained: false,
+        });
+        assert_eq!(bus.retained("/game/state"), Some("underway".to_string()));
+        assert_eq!(*received.lock().unwrap(), vec![("/players/bob/fire".to_string(), "{}".to_string())]);
+    }
+
+    #[test]
+    fn test_protobuf_signed_salvo() {
+        let salvo = vec![
+            SalvoShot { target: String::from("henkie"), x: 1, y: 2 },
+            SalvoShot { target: String::from("bassie"), x: 3, y: 4 },
+        ];
+        let signed = sign_with(salvo.clone(), KEY.deref(), Encoding::Protobuf).expect("Unable to sign!");
+
+        let verified: Vec<SalvoShot> = verify_with(&signed, KEY.deref(), Encoding::Protobuf).expect("Unable to verify!");
+        assert_eq!(verified, salvo);
+        let verified: Result<Vec<SalvoShot>> = verify_with(&signed, "Cockadoodlesploo", Encoding::Protobuf);
+        assert!(matches!(verified, Err(TranslationError::VerificationError(_))));
+        let verified: Result<Vec<SalvoShot>> = verify_with(&signed, KEY.deref(), Encoding::Json);
+        assert!(verified.is_err());
+    }
+
+    #[test]
+    fn test_protobuf_payloads() {
+        let point = Point::new(3, 7);
+        let encoded = serialize_with(&point, Encoding::Protobuf).unwrap();
+        assert_ne!(encoded, serialize_with(&point, Encoding::Json).unwrap());
+        let decoded: Point = deserialize_with(&encoded, Encoding::Protobuf).unwrap();
+        assert_eq!(decoded, point);
+
+        let turn = serialize_with(&Turn::new(String::from("henkie"), 42, 3), Encoding::Json).unwrap();
+        assert_eq!(turn, serialize(&Turn::new(String::from("henkie"), 42, 3)).unwrap());
+        let invalid: Result<Point> = deserialize_with(&String::from("not base64!"), Encoding::Protobuf);
+        assert!(invalid.is_err());
+
+        let ship = Ship::new(point, Vertical, Class::default()).rotated(255, false);
+        let encoded = serialize_with(&ship, Encoding::Protobuf).unwrap();
+        let invalid: Result<Ship> = deserialize_with(&encoded, Encoding::Protobuf);
+        assert!(matches!(invalid, Err(TranslationError::InvalidMessage(_))));
+    }
+
+    /// Runs a client on a runtime of its own, since it blocks worker threads while waiting for messages.
+    fn connect(id: &str, port: u16) -> MosquittoArc {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let id = id.to_string();
+        std::thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_multi_thread().worker_threads(2).enable_all().build().unwrap();
+            runtime.block_on(async move {
+                let (mqtt, future) = MosquittoArc::new(id, "localhost", port, "tester");
+                sender.send(mqtt).unwrap();
+                let (result_1, _result_2, result_3) = future.await;
+                result_1.unwrap();
+                result_3.unwrap();
+            });
+        });
+        receiver.recv().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_to_broker() {
+        let mut mqtt = connect("reconnecting", 18832);
+        let received = Arc::new(Mutex::new(vec![]));
+        for topic in &["/game/hits", "/connections/reconnecting"] {
+            let received_by_callback = received.clone();
+            mqtt.subscribe(*topic, move |topic, payload| {
+                received_by_callback.lock().unwrap().push((topic.clone(), payload.clone()))
+            });
+        }
+        mqtt.publish("/game/hits", "2");
+
+        // Let a few connection attempts fail before the broker shows up.
+        sleep(Duration::from_millis(1200)).await;
+        Broker::start(18832).await.expect("Unable to start the broker");
+        timeout(Duration::from_secs(10), async {
+            while received.lock().unwrap().len() < 2 {
+                sleep(Duration::from_millis(50)).await;
+            }
+        }).await.expect("Nothing received after reconnecting");
+
+        let mut received = received.lock().unwrap().clone();
+        received.sort();
+        assert_eq!(received, vec![
+            ("/connections/reconnecting".to_string(), "online".to_string()),
+            ("/game/hits".to_string(), "2".to_string()),
+        ]);
+        mqtt.stop();
+    }
 }
@@ -2,9 +2,12 @@ use std::string::FromUtf8Error;
 
 use base64::DecodeError;
 use simplecrypt::DecryptionError;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use core::result;
 use std::fmt::Debug;
+use prost::Message;
+use crate::messaging::protobuf::Signed;
+use crate::model::encoding::Encoding;
 
 #[derive(Debug)]
 pub enum TranslationError {
@@ -12,7 +15,15 @@ pub enum TranslationError {
     Base64Error(DecodeError),
     Utf8Error(FromUtf8Error),
     JsonError(serde_json::error::Error),
-    VerificationError(String)
+    VerificationError(String),
+    ProtobufError(prost::DecodeError),
+    InvalidMessage(String),
+}
+
+impl From<prost::DecodeError> for TranslationError {
+    fn from(error: prost::DecodeError) -> Self {
+        TranslationError::ProtobufError(error)
+    }
 }
 
 pub type Result<T> = result::Result<T, TranslationError>;
@@ -59,19 +70,79 @@ pub fn decrypt(input: impl Into<String>, key: impl Into<String>) -> Result<Strin
         )
 }
 
-pub fn verify<'a, T>(signed_data: impl Into<&'a String>, key: impl Into<String>) -> Result<T> where T: Deserialize<'a> + ?Sized + Serialize {
-    let signed_message: SignedMessage<T> = deserialize(signed_data)?;
-    let serialized_data = serialize(&signed_message.data)?;
-    let key: String = key.into();
-    let decrypted_sign = decrypt(&signed_message.sign, &key)
+fn check_sign(sign: &String, key: impl Into<String>, data: &String) -> Result<()> {
+    let decrypted_sign = decrypt(sign, key)
         .map_err(|_| TranslationError::VerificationError(
-            format!("Key {} incorrect for decrypting sign {}", key, &signed_message.sign)
+            format!("Key incorrect for decrypting sign {}", sign)
         ))?;
-    if decrypted_sign == serialized_data {
-        Ok(signed_message.data)
+    if decrypted_sign == *data {
+        Ok(())
     } else {
         Err(TranslationError::VerificationError(
-            format!("Decrypted sign {} does not equal the data: {}", decrypted_sign, serialized_data)
+            format!("Decrypted sign {} does not equal the data: {}", decrypted_sign, data)
         ))
     }
 }
+
+pub fn verify<'a, T>(signed_data: impl Into<&'a String>, key: impl Into<String>) -> Result<T> where T: Deserialize<'a> + Serialize {
+    let signed_message: SignedMessage<T> = deserialize(signed_data)?;
+    let serialized_data = serialize(&signed_message.data)?;
+    check_sign(&signed_message.sign, key, &serialized_data)?;
+    Ok(signed_message.data)
+}
+
+/// Payloads that can be sent as a protobuf message, as well as JSON.
+pub trait IntoMessage {
+    type Message: Message;
+
+    fn to_message(&self) -> Self::Message;
+}
+
+/// Payloads that can be received as a protobuf message, as well as JSON.
+pub trait FromMessage: Sized {
+    type Message: Message + Default;
+
+    fn from_message(message: Self::Message) -> Result<Self>;
+}
+
+fn decode_message<T: FromMessage>(bytes: &[u8]) -> Result<T> {
+    T::from_message(T::Message::decode(bytes)?)
+}
+
+pub fn serialize_with<T>(input: &T, encoding: Encoding) -> Result<String> where T: Serialize + IntoMessage {
+    match encoding {
+        Encoding::Json => serialize(input),
+        Encoding::Protobuf => Ok(encode(input.to_message().encode_to_vec())),
+    }
+}
+
+pub fn deserialize_with<T>(input: &String, encoding: Encoding) -> Result<T> where T: DeserializeOwned + FromMessage {
+    match encoding {
+        Encoding::Json => deserialize(input),
+        Encoding::Protobuf => decode_message(&decode(input)?),
+    }
+}
+
+pub fn sign_with<T>(data: T, key: impl Into<String>, encoding: Encoding) -> Result<String> where T: Serialize + IntoMessage {
+    match encoding {
+        Encoding::Json => sign(data, key),
+        Encoding::Protobuf => {
+            let data = data.to_message().encode_to_vec();
+            let sign = encrypt(encode(&data), key);
+            Ok(encode(Signed { data, sign }.encode_to_vec()))
+        }
+    }
+}
+
+/// Protobuf payloads are signed over the base64 text of the data, JSON payloads over the JSON text.
+pub fn verify_with<T>(signed_data: &String, key: impl Into<String>, encoding: Encoding) -> Result<T>
+    where T: DeserializeOwned + Serialize + FromMessage {
+    match encoding {
+        Encoding::Json => verify(signed_data, key),
+        Encoding::Protobuf => {
+            let signed = Signed::decode(decode(signed_data)?.as_slice())?;
+            check_sign(&signed.sign, key, &encode(&signed.data))?;
+            decode_message(&signed.data)
+        }
+    }
+}
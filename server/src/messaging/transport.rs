@@ -0,0 +1,25 @@
+/// A publish/subscribe connection the engine talks through, be it a broker or an in-process bus.
+pub trait Transport: Clone + Send + 'static {
+    fn subscribe(&mut self, topic: impl Into<String>, callback: impl FnMut(&String, &String) + Send + 'static);
+    fn unsubscribe(&mut self, topic: impl Into<String>);
+    fn publish(&self, topic: impl Into<String>, payload: impl Into<String>);
+    fn retain(&self, topic: impl Into<String>, payload: impl Into<String>);
+    fn clear(&self, topic: impl Into<String>);
+    /// Messages currently retained through this transport on topics matching the filter.
+    fn retained_matching(&self, filter: &str) -> Vec<(String, String)>;
+}
+
+/// Whether a topic matches a subscription filter, with `+` matching one level and `#` all remaining levels.
+pub fn matches(filter: &str, topic: &str) -> bool {
+    let mut filter = filter.split('/');
+    let mut topic = topic.split('/');
+    loop {
+        match (filter.next(), topic.next()) {
+            (Some("#"), _) => return true,
+            (Some("+"), Some(_)) => {}
+            (Some(level), Some(other)) if level == other => {}
+            (None, None) => return true,
+            _ => return false,
+        }
+    }
+}
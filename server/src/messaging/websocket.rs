@@ -0,0 +1,400 @@
+use crate::messaging::transport::{matches, Transport};
+use crate::messaging::translate::{serialize, deserialize};
+
+use bytes::BytesMut;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+use std::mem;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, tcp::OwnedReadHalf};
+use tokio::sync::mpsc::{self, UnboundedSender};
+use log::{info, error};
+
+const MAX_FRAME_SIZE: usize = 1024 * 1024;
+const MAX_HANDSHAKE_SIZE: usize = 8 * 1024;
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+const OPCODE_CONTINUATION: u8 = 0x0;
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xA;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Request {
+    Subscribe { topic: String },
+    Unsubscribe { topic: String },
+    Publish {
+        topic: String,
+        payload: String,
+        #[serde(default)]
+        retain: bool,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Response {
+    Message { topic: String, payload: String, retained: bool },
+    Error { message: String },
+}
+
+enum Outgoing {
+    Text(String),
+    Pong(Vec<u8>),
+    Close,
+}
+
+struct Frame {
+    fin: bool,
+    opcode: u8,
+    payload: Vec<u8>,
+}
+
+struct Connection {
+    sender: UnboundedSender<Outgoing>,
+    filters: Vec<String>,
+}
+
+#[derive(Default)]
+struct State {
+    next_id: usize,
+    connections: BTreeMap<usize, Connection>,
+}
+
+/// Bridges WebSocket connections to a transport, so browser clients can take part without an MQTT client.
+///
+/// Every connection has its own subscriptions. Subscribing delivers the messages currently retained through the
+/// transport first, like a broker would, and everything published on a matching topic afterwards.
+#[derive(Clone)]
+pub struct Gateway {
+    state: Arc<Mutex<State>>,
+}
+
+impl Gateway {
+    /// Starts listening on the given port, connections are served in the background.
+    pub async fn start<T: Transport>(port: u16, mut transport: T) -> io::Result<Gateway> {
+        let listener = TcpListener::bind(("0.0.0.0", port)).await?;
+        info!("WebSocket gateway listening on port {}", port);
+        let gateway = Gateway {
+            state: Arc::new(Mutex::new(State::default())),
+        };
+        let routing = gateway.clone();
+        transport.subscribe("#", move |topic, payload| routing.route(topic, payload));
+
+        let accepting = gateway.clone();
+        tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, address)) => {
+                        info!("Gateway accepted a connection from {}", address);
+                        let gateway = accepting.clone();
+                        let transport = transport.clone();
+                        tokio::spawn(async move {
+                            if let Err(error) = gateway.serve(stream, transport).await {
+                                info!("Gateway connection from {} closed: {}", address, error);
+                            }
+                        });
+                    }
+                    Err(error) => error!("Gateway unable to accept a connection: {:?}", error),
+                }
+            }
+        });
+        Ok(gateway)
+    }
+
+    async fn serve<T: Transport>(&self, stream: TcpStream, mut transport: T) -> Result<(), GatewayError> {
+        let (mut reader, mut writer) = stream.into_split();
+        let mut buffer = BytesMut::new();
+
+        match handshake(&mut reader, &mut buffer).await {
+            Ok(key) => {
+                let response = format!(
+                    "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+                    accept_key(&key)
+                );
+                writer.write_all(response.as_bytes()).await?;
+            }
+            Err(error) => {
+                writer.write_all(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").await?;
+                return Err(error);
+            }
+        }
+
+        let (sender, mut receiver) = mpsc::unbounded_channel::<Outgoing>();
+        let id = {
+            let mut state = self.state.lock().unwrap();
+            let id = state.next_id;
+            state.next_id += 1;
+            state.connections.insert(id, Connection { sender: sender.clone(), filters: vec![] });
+            id
+        };
+
+        tokio::spawn(async move {
+            while let Some(outgoing) = receiver.recv().await {
+                let frame = match &outgoing {
+                    Outgoing::Text(text) => encode_frame(OPCODE_TEXT, text.as_bytes()),
+                    Outgoing::Pong(payload) => encode_frame(OPCODE_PONG, payload),
+                    Outgoing::Close => encode_frame(OPCODE_CLOSE, &[]),
+                };
+                if writer.write_all(&frame).await.is_err() {
+                    break;
+                }
+                if let Outgoing::Close = outgoing {
+                    break;
+                }
+            }
+        });
+
+        let result = self.handle(id, &mut transport, &sender, &mut reader, &mut buffer).await;
+        self.state.lock().unwrap().connections.remove(&id);
+        result
+    }
+
+    async fn handle<T: Transport>(
+        &self, id: usize, transport: &mut T, sender: &UnboundedSender<Outgoing>, reader: &mut OwnedReadHalf,
+        buffer: &mut BytesMut
+    ) -> Result<(), GatewayError> {
+        let mut message = vec![];
+        loop {
+            let frame = read_frame(reader, buffer).await?;
+            match frame.opcode {
+                OPCODE_TEXT | OPCODE_CONTINUATION => {
+                    message.extend(frame.payload);
+                    if message.len() > MAX_FRAME_SIZE {
+                        return Err(GatewayError::TooLarge);
+                    }
+                    if frame.fin {
+                        let text = String::from_utf8(mem::take(&mut message)).map_err(|_| GatewayError::Protocol)?;
+                        self.request(id, transport, sender, &text);
+                    }
+                }
+                OPCODE_PING => { sender.send(Outgoing::Pong(frame.payload)).ok(); }
+                OPCODE_PONG => {}
+                OPCODE_CLOSE => {
+                    sender.send(Outgoing::Close).ok();
+                    return Ok(());
+                }
+                _ => {
+                    sender.send(Outgoing::Close).ok();
+                    return Err(GatewayError::Protocol);
+                }
+            }
+        }
+    }
+
+    fn request<T: Transport>(&self, id: usize, transport: &T, sender: &UnboundedSender<Outgoing>, text: &String) {
+        match deserialize(text) {
+            Ok(Request::Subscribe { topic }) => self.subscribe(id, transport, topic),
+            Ok(Request::Unsubscribe { topic }) => {
+                if let Some(connection) = self.state.lock().unwrap().connections.get_mut(&id) {
+                    connection.filters.retain(|filter| *filter != topic);
+                }
+            }
+            Ok(Request::Publish { topic, payload, retain: true }) => transport.retain(topic, payload),
+            Ok(Request::Publish { topic, payload, retain: false }) => transport.publish(topic, payload),
+            Err(error) => send(sender, &Response::Error {
+                message: format!("Unable to parse request: {:?}", error),
+            }),
+        }
+    }
+
+    fn subscribe<T: Transport>(&self, id: usize, transport: &T, filter: String) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(connection) = state.connections.get_mut(&id) {
+            for (topic, payload) in transport.retained_matching(&filter) {
+                send(&connection.sender, &Response::Message { topic, payload, retained: true });
+            }
+            if !connection.filters.contains(&filter) {
+                connection.filters.push(filter);
+            }
+        }
+    }
+
+    fn route(&self, topic: &str, payload: &str) {
+        let state = self.state.lock().unwrap();
+        for connection in state.connections.values() {
+            if connection.filters.iter().any(|filter| matches(filter, topic)) {
+                send(&connection.sender, &Response::Message {
+                    topic: topic.to_string(), payload: payload.to_string(), retained: false,
+                });
+            }
+        }
+    }
+}
+
+fn send(sender: &UnboundedSender<Outgoing>, response: &Response) {
+    match serialize(response) {
+        Ok(text) => { sender.send(Outgoing::Text(text)).ok(); }
+        Err(error) => error!("Unable to serialize gateway response: {:?}", error),
+    }
+}
+
+#[derive(Debug)]
+enum GatewayError {
+    IoError(io::Error),
+    Handshake(String),
+    Protocol,
+    TooLarge,
+    Closed,
+}
+
+impl Display for GatewayError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            GatewayError::IoError(error) => write!(f, "{}", error),
+            GatewayError::Handshake(reason) => write!(f, "handshake failed: {}", reason),
+            GatewayError::Protocol => write!(f, "protocol violation"),
+            GatewayError::TooLarge => write!(f, "message too large"),
+            GatewayError::Closed => write!(f, "connection closed"),
+        }
+    }
+}
+
+impl From<io::Error> for GatewayError {
+    fn from(error: io::Error) -> Self {
+        GatewayError::IoError(error)
+    }
+}
+
+/// Reads the HTTP upgrade request and returns its `Sec-WebSocket-Key`.
+async fn handshake(reader: &mut OwnedReadHalf, buffer: &mut BytesMut) -> Result<String, GatewayError> {
+    let end = loop {
+        if let Some(end) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            break end + 4;
+        }
+        if buffer.len() > MAX_HANDSHAKE_SIZE {
+            return Err(GatewayError::TooLarge);
+        }
+        if reader.read_buf(buffer).await? == 0 {
+            return Err(GatewayError::Closed);
+        }
+    };
+    let request = buffer.split_to(end);
+    let request = String::from_utf8_lossy(&request);
+    let mut lines = request.lines();
+    if !lines.next().map_or(false, |line| line.starts_with("GET ")) {
+        return Err(GatewayError::Handshake(String::from("Not a GET request")));
+    }
+    let headers: BTreeMap<String, String> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+        .collect();
+    if !headers.get("upgrade").map_or(false, |upgrade| upgrade.eq_ignore_ascii_case("websocket")) {
+        return Err(GatewayError::Handshake(String::from("Not a WebSocket upgrade")));
+    }
+    headers.get("sec-websocket-key").cloned()
+        .ok_or_else(|| GatewayError::Handshake(String::from("Missing Sec-WebSocket-Key")))
+}
+
+/// The `Sec-WebSocket-Accept` answer to a handshake key, as specified in RFC 6455.
+pub(crate) fn accept_key(key: &str) -> String {
+    base64::encode(sha1(format!("{}{}", key, WEBSOCKET_GUID).as_bytes()))
+}
+
+async fn read_frame(reader: &mut OwnedReadHalf, buffer: &mut BytesMut) -> Result<Frame, GatewayError> {
+    loop {
+        if let Some(frame) = parse_frame(buffer)? {
+            return Ok(frame);
+        }
+        if reader.read_buf(buffer).await? == 0 {
+            return Err(GatewayError::Closed);
+        }
+    }
+}
+
+fn parse_frame(buffer: &mut BytesMut) -> Result<Option<Frame>, GatewayError> {
+    if buffer.len() < 2 {
+        return Ok(None);
+    }
+    // Frames from clients are always masked.
+    if buffer[1] & 0x80 == 0 {
+        return Err(GatewayError::Protocol);
+    }
+    let (length, offset) = match buffer[1] & 0x7F {
+        126 if buffer.len() >= 4 => (u16::from_be_bytes([buffer[2], buffer[3]]) as u64, 4),
+        127 if buffer.len() >= 10 => (u64::from_be_bytes(buffer[2..10].try_into().unwrap()), 10),
+        126 | 127 => return Ok(None),
+        length => (length as u64, 2),
+    };
+    if length > MAX_FRAME_SIZE as u64 {
+        return Err(GatewayError::TooLarge);
+    }
+    let length = length as usize;
+    if buffer.len() < offset + 4 + length {
+        return Ok(None);
+    }
+    let frame = buffer.split_to(offset + 4 + length);
+    let mask = &frame[offset..offset + 4];
+    Ok(Some(Frame {
+        fin: frame[0] & 0x80 != 0,
+        opcode: frame[0] & 0x0F,
+        payload: frame[offset + 4..].iter().enumerate().map(|(index, byte)| byte ^ mask[index % 4]).collect(),
+    }))
+}
+
+fn encode_frame(opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        length if length < 126 => frame.push(length as u8),
+        length if length <= u16::MAX as usize => {
+            frame.push(126);
+            frame.extend_from_slice(&(length as u16).to_be_bytes());
+        }
+        length => {
+            frame.push(127);
+            frame.extend_from_slice(&(length as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    frame
+}
+
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut state: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+
+    for block in message.chunks(64) {
+        let mut words = [0u32; 80];
+        for (word, bytes) in words.iter_mut().zip(block.chunks(4)) {
+            *word = u32::from_be_bytes(bytes.try_into().unwrap());
+        }
+        for index in 16..80 {
+            words[index] = (words[index - 3] ^ words[index - 8] ^ words[index - 14] ^ words[index - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = state;
+        for (index, word) in words.iter().enumerate() {
+            let (f, k) = match index {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let temporary = a.rotate_left(5).wrapping_add(f).wrapping_add(e).wrapping_add(k).wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temporary;
+        }
+        for (value, added) in state.iter_mut().zip([a, b, c, d, e].iter()) {
+            *value = value.wrapping_add(*added);
+        }
+    }
+
+    let mut digest = [0u8; 20];
+    for (bytes, value) in digest.chunks_mut(4).zip(state.iter()) {
+        bytes.copy_from_slice(&value.to_be_bytes());
+    }
+    digest
+}
@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+#[derive(Serialize, Deserialize, Debug, PartialOrd, PartialEq, Clone, Copy, Default)]
+pub enum Adjacency {
+    #[default]
+    None,
+    Orthogonal,
+    Diagonal,
+}
+
+impl FromStr for Adjacency {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "none" => Ok(Adjacency::None),
+            "orthogonal" => Ok(Adjacency::Orthogonal),
+            "diagonal" => Ok(Adjacency::Diagonal),
+            other => Err(format!("Unknown adjacency rule: {}", other)),
+        }
+    }
+}
+
+impl Adjacency {
+    /// Whether two distinct cells are too close to each other under this rule.
+    pub fn neighbours(&self, cell: (u16, u16), other: (u16, u16)) -> bool {
+        let dx = (cell.0 as i32 - other.0 as i32).abs();
+        let dy = (cell.1 as i32 - other.1 as i32).abs();
+        match self {
+            Adjacency::None => false,
+            Adjacency::Orthogonal => dx + dy == 1,
+            Adjacency::Diagonal => dx.max(dy) == 1,
+        }
+    }
+}
@@ -1,17 +1,20 @@
 use serde::{Deserialize, Serialize};
-use crate::model::class::Class::{Carrier, Battleship, Destroyer, Submarine, PatrolBoat};
-use strum_macros::EnumIter;
 use std::fmt::{Display, Formatter};
 use core::fmt;
 use crate::model::occupation::Occupation;
+use crate::model::point::Point;
 
-#[derive(Serialize, Deserialize, Debug, PartialOrd, PartialEq, Clone, Copy, EnumIter)]
-pub enum Class {
-    Carrier([bool; 5]),
-    Battleship([bool; 4]),
-    Destroyer([bool; 3]),
-    Submarine([bool; 3]),
-    PatrolBoat([bool; 2]),
+#[derive(Serialize, Deserialize, Debug, PartialOrd, PartialEq, Clone, Default)]
+pub struct Class {
+    pub name: String,
+    #[serde(default)]
+    pub length: u8,
+    pub token: String,
+    /// Cells of the ship, a straight line of `length` cells when left empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shape: Vec<Point>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    hits: Vec<u8>,
 }
 
 impl Into<String> for Class {
@@ -26,72 +29,65 @@ impl Display for Class {
     }
 }
 
-impl Into<Class> for &str {
-    fn into(self) -> Class {
-        match self {
-            "carrier" => Carrier([false; 5]),
-            "battleship" => Battleship([false; 4]),
-            "destroyer" => Destroyer([false; 3]),
-            "submarine" => Submarine([false; 3]),
-            "patrolboat" => PatrolBoat([false; 2]),
-            _ => "carrier".into()
+impl Class {
+    pub fn new(name: impl Into<String>, length: u8, token: impl Into<String>) -> Self {
+        Class {
+            name: name.into(),
+            length,
+            token: token.into(),
+            shape: vec![],
+            hits: vec![],
         }
     }
-}
 
-impl Default for Class {
-    fn default() -> Self {
-        Battleship([false; 4])
+    pub fn shaped(name: impl Into<String>, token: impl Into<String>, shape: Vec<Point>) -> Self {
+        Class {
+            length: shape.len() as u8,
+            shape,
+            ..Class::new(name, 0, token)
+        }
+    }
+
+    pub fn standard_fleet() -> Vec<Class> {
+        vec![
+            Class::new("carrier", 5, "C"),
+            Class::new("battleship", 4, "B"),
+            Class::new("destroyer", 3, "D"),
+            Class::new("submarine", 3, "S"),
+            Class::new("patrolboat", 2, "P"),
+        ]
     }
-}
 
-impl Class {
     pub(crate) fn size(&self) -> u8 {
-        let length = match self {
-            Carrier(places) => places.len(),
-            Battleship(places) => places.len(),
-            Destroyer(places) => places.len(),
-            Submarine(places) => places.len(),
-            PatrolBoat(places) => places.len(),
-        };
-        length as u8
+        self.length
+    }
+
+    pub fn shape(&self) -> Vec<Point> {
+        if self.shape.is_empty() {
+            (0..self.length).map(|x| Point::new(x, 0)).collect()
+        } else {
+            self.shape.clone()
+        }
     }
 
     pub fn name(&self) -> String {
-        String::from(match self {
-            Carrier(_) => "carrier",
-            Battleship(_) => "battleship",
-            Destroyer(_) => "destroyer",
-            Submarine(_) => "submarine",
-            PatrolBoat(_) => "patrolboat",
-        })
+        self.name.clone()
     }
 
     pub fn map_token(&self) -> &str {
-        match self {
-            Class::Carrier(_) => "C",
-            Class::Battleship(_) => "B",
-            Class::Destroyer(_) => "D",
-            Class::Submarine(_) => "S",
-            Class::PatrolBoat(_) => "P",
-        }
+        &self.token
     }
 
-    fn get_place<const COUNT: usize>(places: &[bool; COUNT], x: usize) -> Option<bool> {
-        if x >= COUNT {
-            None
-        } else {
-            Some(places[x])
-        }
+    /// Whether this is the same kind of ship, regardless of where it was hit.
+    pub fn same_class(&self, other: &Class) -> bool {
+        self.name == other.name
     }
 
     fn place(&self, x: usize) -> Option<bool> {
-        match self {
-            Carrier(places) => Self::get_place(places, x),
-            Battleship(places) => Self::get_place(places, x),
-            Destroyer(places) => Self::get_place(places, x),
-            Submarine(places) => Self::get_place(places, x),
-            PatrolBoat(places) => Self::get_place(places, x),
+        if x >= self.length as usize {
+            None
+        } else {
+            Some(self.hits.contains(&(x as u8)))
         }
     }
 
@@ -107,41 +103,20 @@ impl Class {
         }
     }
 
-    fn mark<const COUNT: usize>(places: &[bool; COUNT], x: u8) -> [bool; COUNT] {
-        if x >= COUNT as u8 {
-            return places.clone()
-        }
-        let mut places = places.clone();
-        places[x as usize] = true;
-        places.clone()
-    }
-
     pub(crate) fn shoot(&self, x: u8) -> Class {
-        match self {
-            Carrier(places) => Carrier(Class::mark(places, x)),
-            Battleship(places) => Battleship(Class::mark(places, x)),
-            Destroyer(places) => Destroyer(Class::mark(places, x)),
-            Submarine(places) => Submarine(Class::mark(places, x)),
-            PatrolBoat(places) => PatrolBoat(Class::mark(places, x)),
+        let mut class = self.clone();
+        if x < self.length && !self.hits.contains(&x) {
+            class.hits.push(x);
         }
+        class
     }
 
-    fn all_hit<const COUNT: usize>(places: &[bool; COUNT]) -> bool {
-        for index in 0..COUNT {
-            if !places[index] {
-                return false
-            }
-        }
-        true
+    /// Number of cells that have not been hit yet.
+    pub(crate) fn remaining(&self) -> u8 {
+        self.length - self.hits.len() as u8
     }
 
     pub(crate) fn is_sunk(&self) -> bool {
-        match self {
-            Carrier(places) => Class::all_hit(places),
-            Battleship(places) => Class::all_hit(places),
-            Destroyer(places) => Class::all_hit(places),
-            Submarine(places) => Class::all_hit(places),
-            PatrolBoat(places) => Class::all_hit(places),
-        }
+        (0..self.length).all(|x| self.hits.contains(&x))
     }
 }
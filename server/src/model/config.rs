@@ -0,0 +1,166 @@
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::fs;
+use crate::model::adjacency::Adjacency;
+use crate::model::class::Class;
+use crate::model::disclosure::Disclosure;
+use crate::model::encoding::Encoding;
+use crate::model::friendlyfire::FriendlyFire;
+use crate::model::targetpolicy::TargetPolicy;
+use crate::model::size::Size;
+use crate::model::ship::Ship;
+use crate::model::point::Point;
+use crate::model::orientation::Orientation;
+use crate::model::salvosize::SalvoSize;
+
+#[derive(Serialize, Deserialize, Debug, PartialOrd, PartialEq, Clone, Copy)]
+pub enum StragglerPolicy {
+    Drop,
+    RandomFleet,
+}
+
+impl FromStr for StragglerPolicy {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "drop" => Ok(StragglerPolicy::Drop),
+            "random" => Ok(StragglerPolicy::RandomFleet),
+            other => Err(format!("Unknown straggler policy: {}", other)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialOrd, PartialEq, Clone)]
+#[serde(default)]
+pub struct Config {
+    pub allow_plaintext: bool,
+    pub placement_timeout: u64,
+    pub stragglers: StragglerPolicy,
+    pub turn_timeout: u64,
+    pub max_timeouts: u8,
+    pub cool_down: u64,
+    pub hits_breakdown: bool,
+    pub snapshots: Option<String>,
+    pub resume: bool,
+    pub journal: Option<String>,
+    pub classes: Vec<Class>,
+    pub size: Size,
+    pub adjacency: Adjacency,
+    pub disclosure: Disclosure,
+    pub salvo_size: SalvoSize,
+    pub friendly_fire: FriendlyFire,
+    pub reject_repeats: bool,
+    pub self_targeting: TargetPolicy,
+    pub defeated_targets: TargetPolicy,
+    pub embedded_broker: Option<u16>,
+    pub websocket: Option<u16>,
+    pub encoding: Encoding,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            allow_plaintext: false,
+            placement_timeout: 60,
+            stragglers: StragglerPolicy::Drop,
+            turn_timeout: 10,
+            max_timeouts: 3,
+            cool_down: 300,
+            hits_breakdown: false,
+            snapshots: None,
+            resume: false,
+            journal: None,
+            classes: Class::standard_fleet(),
+            size: Size::default(),
+            adjacency: Adjacency::None,
+            disclosure: Disclosure::Salvo,
+            salvo_size: SalvoSize::Ships,
+            friendly_fire: FriendlyFire::Allowed,
+            reject_repeats: false,
+            self_targeting: TargetPolicy::Allow,
+            defeated_targets: TargetPolicy::Allow,
+            embedded_broker: None,
+            websocket: None,
+            encoding: Encoding::Json,
+        }
+    }
+}
+
+fn value<'a, T: FromStr>(args: &mut impl Iterator<Item=&'a String>, flag: &str) -> Result<T, String> {
+    let value = args.next().ok_or(format!("Option {} requires a value", flag))?;
+    value.parse().map_err(|_| format!("Invalid value for option {}: {}", flag, value))
+}
+
+fn fleet(path: &str) -> Result<Vec<Class>, String> {
+    let json = fs::read_to_string(path).map_err(|error| format!("Unable to read fleet {}: {}", path, error))?;
+    let classes: Vec<Class> = serde_json::from_str(&json)
+        .map_err(|error| format!("Unable to parse fleet {}: {}", path, error))?;
+    let classes: Vec<Class> = classes.into_iter()
+        .map(|class| if class.shape.is_empty() {
+            class
+        } else {
+            Class::shaped(class.name, class.token, class.shape)
+        })
+        .collect();
+    if classes.is_empty() {
+        return Err(format!("Fleet {} contains no ships", path));
+    }
+    for (index, class) in classes.iter().enumerate() {
+        if class.length == 0 || class.name.is_empty() || class.name.contains(|character| character == '/' || character == '+' || character == '#') {
+            return Err(format!("Invalid ship class in fleet {}: {:?}", path, class));
+        }
+        if class.shape.iter().enumerate().any(|(cell, point)| class.shape[..cell].contains(point)) {
+            return Err(format!("Ship class {} has overlapping cells in fleet {}", class, path));
+        }
+        if classes[..index].iter().any(|other| other.same_class(class)) {
+            return Err(format!("Ship class {} appears more than once in fleet {}", class, path));
+        }
+    }
+    Ok(classes)
+}
+
+/// Whether a ship of this class can be placed on an empty board of the given size, in any rotation.
+fn fits(class: &Class, size: &Size) -> bool {
+    (0..4).any(|rotation| {
+        Ship::new(Point::new(0, 0), Orientation::Horizontal, class.clone()).rotated(rotation, false).inside(size)
+    })
+}
+
+impl Config {
+    pub fn from_args(args: &[String]) -> Result<Config, String> {
+        let mut config = Config::default();
+        let mut args = args.iter();
+        while let Some(flag) = args.next() {
+            match flag.as_str() {
+                "--allow-plaintext" => config.allow_plaintext = true,
+                "--placement-timeout" => config.placement_timeout = value(&mut args, flag)?,
+                "--stragglers" => config.stragglers = value(&mut args, flag)?,
+                "--turn-timeout" => config.turn_timeout = value(&mut args, flag)?,
+                "--max-timeouts" => config.max_timeouts = value(&mut args, flag)?,
+                "--cool-down" => config.cool_down = value(&mut args, flag)?,
+                "--hits-breakdown" => config.hits_breakdown = true,
+                "--snapshots" => config.snapshots = Some(value(&mut args, flag)?),
+                "--resume" => config.resume = true,
+                "--journal" => config.journal = Some(value(&mut args, flag)?),
+                "--size" => config.size = value(&mut args, flag)?,
+                "--adjacency" => config.adjacency = value(&mut args, flag)?,
+                "--disclosure" => config.disclosure = value(&mut args, flag)?,
+                "--salvo-size" => config.salvo_size = value(&mut args, flag)?,
+                "--friendly-fire" => config.friendly_fire = value(&mut args, flag)?,
+                "--reject-repeats" => config.reject_repeats = true,
+                "--self-targeting" => config.self_targeting = value(&mut args, flag)?,
+                "--defeated-targets" => config.defeated_targets = value(&mut args, flag)?,
+                "--embedded-broker" => config.embedded_broker = Some(value(&mut args, flag)?),
+                "--websocket" => config.websocket = Some(value(&mut args, flag)?),
+                "--encoding" => config.encoding = value(&mut args, flag)?,
+                "--fleet" => config.classes = fleet(&value::<String>(&mut args, flag)?)?,
+                other => return Err(format!("Unknown option: {}", other)),
+            }
+        }
+        if let Some(class) = config.classes.iter().find(|class| !fits(class, &config.size)) {
+            return Err(format!("Ship class {} does not fit on a board of {}", class, config.size));
+        }
+        Ok(config)
+    }
+}
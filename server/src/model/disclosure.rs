@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+#[derive(Serialize, Deserialize, Debug, PartialOrd, PartialEq, Clone, Copy, Default)]
+pub enum Disclosure {
+    Classic,
+    #[default]
+    Salvo,
+    HiddenSink,
+}
+
+impl FromStr for Disclosure {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "classic" => Ok(Disclosure::Classic),
+            "salvo" => Ok(Disclosure::Salvo),
+            "hidden-sink" => Ok(Disclosure::HiddenSink),
+            other => Err(format!("Unknown disclosure mode: {}", other)),
+        }
+    }
+}
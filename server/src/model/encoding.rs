@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use core::fmt;
+
+#[derive(Serialize, Deserialize, Debug, PartialOrd, PartialEq, Clone, Copy, Default)]
+pub enum Encoding {
+    #[default]
+    Json,
+    Protobuf,
+}
+
+impl Display for Encoding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Encoding::Json => write!(f, "json"),
+            Encoding::Protobuf => write!(f, "protobuf"),
+        }
+    }
+}
+
+impl FromStr for Encoding {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "json" => Ok(Encoding::Json),
+            "protobuf" => Ok(Encoding::Protobuf),
+            other => Err(format!("Unknown encoding: {}", other)),
+        }
+    }
+}
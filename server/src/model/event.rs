@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+use crate::model::{
+    game::Game,
+    gamestate::GameState,
+    player::Player,
+    ship::Ship,
+    class::Class,
+    point::Point,
+    impact::Impact,
+    size::Size,
+    adjacency::Adjacency,
+};
+
+#[derive(Serialize, Deserialize, Debug, PartialOrd, PartialEq, Clone)]
+pub enum Event {
+    Created {
+        size: Size,
+        #[serde(default = "Class::standard_fleet")]
+        classes: Vec<Class>,
+        #[serde(default)]
+        adjacency: Adjacency,
+    },
+    Registered {
+        player: String,
+        #[serde(default)]
+        team: Option<String>,
+    },
+    Placed { player: String, ship: Ship },
+    Dropped { player: String },
+    TurnChanged { player: String },
+    Shot { shooter: String, target: String, coordinates: Point, impact: Impact<Ship> },
+    Sunk { player: String, class: Class },
+    TimedOut { player: String },
+    Defeated { player: String },
+    GameOver { winner: String },
+    Reset,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialOrd, PartialEq, Clone)]
+pub struct Entry {
+    pub timestamp: u64,
+    pub event: Event,
+}
+
+impl Entry {
+    pub fn new(timestamp: u64, event: Event) -> Self {
+        Entry {
+            timestamp, event
+        }
+    }
+}
+
+impl Event {
+    pub fn apply(&self, game: &mut Game) {
+        match self {
+            Event::Created { size, classes, adjacency } => {
+                let mut config = game.config.clone();
+                config.classes = classes.clone();
+                config.adjacency = *adjacency;
+                *game = Game::new(*size, game.prefix(), config);
+            }
+            Event::Registered { player, team } => game.update_player(
+                Player::new(player.clone(), String::new(), game.config.classes.clone(), game.size, game.config.adjacency)
+                    .in_team(team.clone())
+            ),
+            Event::Placed { player, ship } => {
+                if let Some(Ok(player)) = game.find_player(player).map(|(_, player)| player.place_ship(ship.clone())) {
+                    game.update_player(player);
+                }
+            }
+            Event::Dropped { player } => game.remove_player(player),
+            Event::TurnChanged { player } => {
+                if let Some((index, _)) = game.find_player(player) {
+                    game.state = GameState::Underway((index, player.clone()), 0, 0);
+                    game.turn += 1;
+                    game.salvo.clear();
+                    game.sinks.clear();
+                }
+            }
+            Event::Shot { shooter, target, coordinates, impact } => {
+                game.remember_shot(shooter, target, *coordinates);
+                if let Some((_, player)) = game.find_player(target) {
+                    // A recorded miss stays a miss, even when the cell holds a ship that was spared by the rules.
+                    match impact {
+                        Impact::Miss => {
+                            game.register_shot(target, false);
+                        }
+                        Impact::Hit(_) => {
+                            let impact = player.shoot(coordinates);
+                            game.register_shot(target, matches!(impact, Impact::Hit(_)));
+                            if let Impact::Hit((player, _)) = impact {
+                                game.update_player(player);
+                            }
+                        }
+                    }
+                }
+            }
+            Event::Sunk { .. } => {}
+            Event::TimedOut { player } => {
+                if let Some((_, player)) = game.find_player(player) {
+                    game.update_player(player.timed_out());
+                }
+            }
+            Event::Defeated { player } => {
+                if let Some((_, player)) = game.find_player(player) {
+                    game.update_player(player.forfeit());
+                }
+            }
+            Event::GameOver { winner } => game.state = GameState::Over(winner.clone()),
+            Event::Reset => game.reset(),
+        }
+    }
+}
@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+#[derive(Serialize, Deserialize, Debug, PartialOrd, PartialEq, Clone, Copy, Default)]
+pub enum FriendlyFire {
+    #[default]
+    Allowed,
+    Rejected,
+    Ignored,
+}
+
+impl FromStr for FriendlyFire {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "allowed" => Ok(FriendlyFire::Allowed),
+            "rejected" => Ok(FriendlyFire::Rejected),
+            "ignored" => Ok(FriendlyFire::Ignored),
+            other => Err(format!("Unknown friendly fire policy: {}", other)),
+        }
+    }
+}
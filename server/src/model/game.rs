@@ -6,7 +6,14 @@ use crate::model::gamestate::GameState::Lobby;
 use std::sync::{Arc, Mutex};
 use log::info;
 use crate::model::size::Size;
+use crate::model::config::Config;
+use crate::model::encoding::Encoding;
+use crate::model::stats::Stats;
+use crate::model::class::Class;
+use crate::model::winner::Winner;
+use crate::model::point::Point;
 use delegate::delegate;
+use std::collections::BTreeMap;
 
 #[derive(Serialize, Deserialize, Debug, PartialOrd, PartialEq, Clone)]
 pub struct Game {
@@ -14,15 +21,34 @@ pub struct Game {
     pub players: Vec<Player>,
     pub size: Size,
     pub prefix: String,
+    #[serde(default = "Config::default")]
+    pub config: Config,
+    #[serde(default)]
+    pub placement_deadline: Option<u64>,
+    #[serde(default)]
+    pub turn: u32,
+    #[serde(default)]
+    pub salvo: BTreeMap<String, u8>,
+    #[serde(default)]
+    pub stats: Stats,
+    /// Ships sunk during the current salvo that have not been announced yet.
+    #[serde(default)]
+    pub sinks: Vec<(String, Class)>,
 }
 
 impl Game {
-    pub fn new(size: Size, prefix: String) -> Self {
+    pub fn new(size: Size, prefix: String, config: Config) -> Self {
         Game {
             state: Lobby,
             players: vec![],
             size,
-            prefix
+            prefix,
+            config,
+            placement_deadline: None,
+            turn: 0,
+            salvo: BTreeMap::new(),
+            stats: Stats::default(),
+            sinks: vec![],
         }
     }
 
@@ -30,9 +56,14 @@ impl Game {
         self.prefix.clone()
     }
 
-    pub fn start(&mut self, dice: fn(usize) -> usize) {
+    pub fn start(&mut self, dice: fn(usize) -> usize) -> bool {
+        if self.state != Lobby || self.players.is_empty() {
+            return false;
+        }
         let first_player = dice(self.players.len());
         self.state = GameState::Underway((first_player, self.players[first_player].name.clone()), 0, 0);
+        self.placement_deadline = None;
+        true
     }
 
     pub fn player_count(&self) -> usize {
@@ -63,19 +94,93 @@ impl Game {
         info!("{}", player);
         match self.find_player(&player.name) {
             None => self.players.push(player),
-            Some((index, _)) => {
-                self.players.remove(index);
-                self.players.push(player);
-            }
+            Some((index, _)) => self.players[index] = player,
+        }
+    }
+
+    pub fn remove_player(&mut self, name: impl Into<String>) {
+        if let Some((index, _)) = self.find_player(name) {
+            self.players.remove(index);
         }
     }
 
+    pub fn incomplete_players(&self) -> Vec<Player> {
+        self.players.iter().filter(|player| !player.is_fleet_complete()).cloned().collect()
+    }
+
     pub fn ready_players(&self) -> usize {
         self.players.iter().filter(|player| player.is_fleet_complete()).count()
     }
 
     pub fn active_player_count(&self) -> usize {
-        self.players.iter().filter(|player| player.is_defeated()).count()
+        self.players.iter().filter(|player| !player.is_defeated()).count()
+    }
+
+    pub fn active_players(&self) -> Vec<String> {
+        self.players.iter()
+            .filter(|player| !player.is_defeated())
+            .map(|player| player.name.clone())
+            .collect()
+    }
+
+    /// Teams with at least one player that is not defeated, in order of registration.
+    pub fn active_teams(&self) -> Vec<String> {
+        let mut teams: Vec<String> = vec![];
+        for player in self.players.iter().filter(|player| !player.is_defeated()) {
+            if !teams.contains(&player.team()) {
+                teams.push(player.team());
+            }
+        }
+        teams
+    }
+
+    pub fn teams(&self) -> BTreeMap<String, Vec<String>> {
+        let mut teams: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for player in &self.players {
+            teams.entry(player.team()).or_default().push(player.name.clone());
+        }
+        teams
+    }
+
+    pub fn winner(&self) -> Option<Winner> {
+        if let GameState::Over(team) = &self.state {
+            let members = self.players.iter()
+                .filter(|player| player.team() == *team && !player.is_defeated())
+                .map(|player| player.name.clone())
+                .collect();
+            Some(Winner::new(team.clone(), members))
+        } else {
+            None
+        }
+    }
+
+    pub fn register_shot(&mut self, target: &str, hit: bool) -> u8 {
+        if let GameState::Underway(player, fired_shots, hits) = &self.state {
+            let fired_shots = fired_shots + 1;
+            let hits = if hit {
+                hits + 1
+            } else {
+                *hits
+            };
+            self.state = GameState::Underway(player.clone(), fired_shots, hits);
+            *self.salvo.entry(target.to_string()).or_insert(0) += hit as u8;
+            fired_shots
+        } else {
+            0
+        }
+    }
+
+    /// Uses up a shot of the current salvo without firing it.
+    pub fn consume_shot(&mut self) {
+        if let GameState::Underway(player, fired_shots, hits) = &self.state {
+            self.state = GameState::Underway(player.clone(), fired_shots + 1, *hits);
+        }
+    }
+
+    pub fn remember_shot(&mut self, shooter: &str, target: &str, coordinates: Point) {
+        if let Some(player) = self.players.iter_mut().find(|player| player.name == shooter) {
+            player.fired_at(target, coordinates);
+        }
     }
 
     pub fn next_turn(&mut self) {
@@ -83,7 +188,7 @@ impl Game {
             let mut next_player_index = (index + 1) % self.player_count();
             while next_player_index != *index {
                 if match self.get_player(next_player_index) {
-                    Some(player) => player.is_defeated(),
+                    Some(player) => !player.is_defeated(),
                     _ => false,
                 } {
                     break;
@@ -97,15 +202,25 @@ impl Game {
             if let Some(next_player) = self.get_player(next_player_index) {
                 let next_player_name = next_player.name.clone();
                 self.state = GameState::Underway((next_player_index, next_player_name), 0, 0);
+                self.turn += 1;
+                self.salvo.clear();
+                self.sinks.clear();
             }
         }
     }
 
     pub fn game_over(&mut self) {
-        if let Some(winner) = self.players.iter().find(|player| !player.is_defeated()) {
-            self.state = GameState::Over(winner.name.clone());
+        if let Some(winner) = self.active_teams().first() {
+            self.state = GameState::Over(winner.clone());
         }
     }
+
+    /// The turn counter carries over, so timers left over from the previous game never match a turn of the next one.
+    pub fn reset(&mut self) {
+        let turn = self.turn;
+        *self = Game::new(self.size, self.prefix(), self.config.clone());
+        self.turn = turn;
+    }
 }
 
 pub struct GameArc {
@@ -113,15 +228,21 @@ pub struct GameArc {
 }
 
 impl GameArc {
-    pub fn new(size: Size, prefix: String) -> GameArc {
+    pub fn new(size: Size, prefix: String, config: Config) -> GameArc {
         GameArc {
-            inner: Arc::new(Mutex::new(Game::new(size, prefix))),
+            inner: Arc::new(Mutex::new(Game::new(size, prefix, config))),
         }
     }
 
     delegate! {
         to  self.inner.lock().unwrap() {
-            pub fn start(&mut self, dice: fn(usize) -> usize);
+            pub fn start(&mut self, dice: fn(usize) -> usize) -> bool;
+            pub fn remove_player(&mut self, name: impl Into<String>);
+            pub fn incomplete_players(&self) -> Vec<Player>;
+            pub fn active_players(&self) -> Vec<String>;
+            pub fn active_teams(&self) -> Vec<String>;
+            pub fn teams(&self) -> BTreeMap<String, Vec<String>>;
+            pub fn winner(&self) -> Option<Winner>;
         }
     }
 
@@ -129,6 +250,22 @@ impl GameArc {
         self.inner.lock().unwrap().prefix()
     }
 
+    pub fn size(&self) -> Size {
+        self.inner.lock().unwrap().size
+    }
+
+    pub fn snapshot(&self) -> Game {
+        self.inner.lock().unwrap().clone()
+    }
+
+    pub fn config(&self) -> Config {
+        self.inner.lock().unwrap().config.clone()
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.inner.lock().unwrap().config.encoding
+    }
+
     pub fn player_count(&self) -> usize {
         self.inner.lock().unwrap().player_count()
     }
@@ -157,10 +294,65 @@ impl GameArc {
         self.inner.lock().unwrap().next_turn()
     }
 
+    pub fn register_shot(&mut self, target: &str, hit: bool) -> u8 {
+        self.inner.lock().unwrap().register_shot(target, hit)
+    }
+
+    pub fn consume_shot(&mut self) {
+        self.inner.lock().unwrap().consume_shot()
+    }
+
+    pub fn remember_shot(&mut self, shooter: &str, target: &str, coordinates: Point) {
+        self.inner.lock().unwrap().remember_shot(shooter, target, coordinates)
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.inner.lock().unwrap().stats.clone()
+    }
+
+    pub fn update_stats(&mut self, update: impl FnOnce(&mut Stats)) {
+        update(&mut self.inner.lock().unwrap().stats)
+    }
+
+    pub fn salvo(&self) -> BTreeMap<String, u8> {
+        self.inner.lock().unwrap().salvo.clone()
+    }
+
+    pub fn register_sink(&mut self, player: &str, class: &Class) {
+        self.inner.lock().unwrap().sinks.push((player.to_string(), class.clone()))
+    }
+
+    pub fn take_sinks(&mut self) -> Vec<(String, Class)> {
+        std::mem::take(&mut self.inner.lock().unwrap().sinks)
+    }
+
+    pub fn fired_shots(&self) -> u8 {
+        match self.inner.lock().unwrap().state {
+            GameState::Underway(_, fired_shots, _) => fired_shots,
+            _ => 0,
+        }
+    }
+
+    pub fn turn(&self) -> u32 {
+        self.inner.lock().unwrap().turn
+    }
+
     pub fn game_over(&mut self) {
         self.inner.lock().unwrap().game_over()
     }
 
+    pub fn reset(&mut self) {
+        self.inner.lock().unwrap().reset()
+    }
+
+    pub fn placement_deadline(&self) -> Option<u64> {
+        self.inner.lock().unwrap().placement_deadline
+    }
+
+    pub fn set_placement_deadline(&mut self, deadline: Option<u64>) {
+        self.inner.lock().unwrap().placement_deadline = deadline
+    }
+
     pub fn state(&self) -> GameState {
         self.inner.lock().unwrap().state.clone()
     }
@@ -170,6 +362,14 @@ impl GameArc {
     }
 }
 
+impl From<Game> for GameArc {
+    fn from(game: Game) -> Self {
+        GameArc {
+            inner: Arc::new(Mutex::new(game)),
+        }
+    }
+}
+
 impl Clone for GameArc {
     fn clone(&self) -> Self {
         GameArc {
@@ -7,9 +7,24 @@ use crate::model::point::Point;
 
 #[derive(Debug, PartialOrd, PartialEq, Clone)]
 pub enum GameError {
+    UnknownClass(String),
     ShipAlreadyPlaced(Class),
     ShipOutOfBounds(Point, Orientation, u8),
+    InvalidRotation(u8),
     ShipOverlaps(Ship),
+    ShipTouches(Ship),
+    InvalidSignature,
+    MalformedPayload,
+    ShotOutOfBounds(Point),
+    FriendlyFire(String),
+    RepeatedShot(String, Point),
+    SelfTargeted,
+    TargetDefeated(String),
+    UnknownPlayer(String),
+    NotYourTurn,
+    WrongSalvoSize(u8, usize),
+    PlacementNotDecryptable,
+    PlacementDeadlinePassed,
 }
 
 impl Display for GameError {
@@ -17,6 +32,8 @@ impl Display for GameError {
         write!(
             f, "{}",
             match self {
+                GameError::UnknownClass(class) =>
+                    format!("{} is not a ship class in this game!", class),
                 GameError::ShipAlreadyPlaced(class) =>
                     format!("{} class ship has already been placed!", class),
                 GameError::ShipOutOfBounds(coordinates, orientation, size) =>
@@ -24,11 +41,42 @@ impl Display for GameError {
                         "Ship is not placed (entirely) within the map! Coordinates: {}, orientation: {}, size: {}",
                         coordinates, orientation, size,
                     ),
+                GameError::InvalidRotation(rotation) =>
+                    format!("Ship rotation must be between 0 and 3 quarter turns, received {}!", rotation),
                 GameError::ShipOverlaps(ship) =>
                     format!(
                         "This ship overlaps with ship of class {} at {} (orientation: {})!",
                         ship.class, ship.coordinates, ship.orientation,
                     ),
+                GameError::ShipTouches(ship) =>
+                    format!(
+                        "This ship touches ship of class {} at {} (orientation: {})!",
+                        ship.class, ship.coordinates, ship.orientation,
+                    ),
+                GameError::InvalidSignature =>
+                    "Shot rejected: signature does not match the current player's secret!".to_string(),
+                GameError::MalformedPayload =>
+                    "Shot rejected: the message is not a valid signed message!".to_string(),
+                GameError::ShotOutOfBounds(coordinates) =>
+                    format!("Shot rejected: {} is not within the map!", coordinates),
+                GameError::FriendlyFire(target) =>
+                    format!("Shot rejected: {} is on your own team!", target),
+                GameError::RepeatedShot(target, coordinates) =>
+                    format!("Shot rejected: you already fired at {} on {}'s map!", coordinates, target),
+                GameError::SelfTargeted =>
+                    "Shot rejected: you cannot fire at yourself!".to_string(),
+                GameError::TargetDefeated(target) =>
+                    format!("Shot rejected: {} is already defeated!", target),
+                GameError::UnknownPlayer(target) =>
+                    format!("Shot rejected: {} is not a player in this game!", target),
+                GameError::NotYourTurn =>
+                    "Salvo rejected: it is not your turn!".to_string(),
+                GameError::WrongSalvoSize(expected, received) =>
+                    format!("Salvo rejected: expected {} shots, received {}!", expected, received),
+                GameError::PlacementNotDecryptable =>
+                    "Ship placement could not be decrypted with the player's secret!".to_string(),
+                GameError::PlacementDeadlinePassed =>
+                    "Fleet was not complete when the placement deadline passed!".to_string(),
             },
         )
     }
@@ -23,18 +23,8 @@ impl Display for GameState {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(f, "{}", match self {
             GameState::Lobby => format!("Lobby"),
-            GameState::Underway((_, player), shots, _hits) => format!("{}'s turn. {} shots to go.", player, 5 - shots),
+            GameState::Underway((_, player), shots, _hits) => format!("{}'s turn. {} shots fired.", player, shots),
             GameState::Over(winner) => format!("Game over. {} won!", winner),
         })
     }
 }
-//
-// impl GameState {
-//     pub fn timeout(&self) -> u32 {
-//         match self {
-//             GameState::Lobby => 60,
-//             GameState::Underway(_, _, _) => 10,
-//             GameState::Over(_) => 300,
-//         }
-//     }
-// }
@@ -1,5 +1,11 @@
 
+pub mod adjacency;
 pub mod class;
+pub mod config;
+pub mod disclosure;
+pub mod encoding;
+pub mod event;
+pub mod friendlyfire;
 pub mod game;
 pub mod gamestate;
 pub mod occupation;
@@ -7,10 +13,18 @@ pub mod orientation;
 pub mod player;
 pub mod point;
 pub mod range;
+pub mod rules;
+pub mod salvo;
+pub mod salvosize;
 pub mod size;
 pub mod ship;
+pub mod shotresult;
 pub mod impact;
+pub mod stats;
 pub mod status;
+pub mod targetpolicy;
+pub mod turn;
+pub mod winner;
 pub mod gameerror;
 
 mod tests;
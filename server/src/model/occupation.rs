@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
 use core::fmt;
 
-#[derive(Serialize, Deserialize, Debug, PartialOrd, PartialEq, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, PartialOrd, PartialEq, Clone)]
 pub enum Occupation {
     Ship(Class, bool),
     Sunk(Class),
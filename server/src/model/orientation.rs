@@ -3,8 +3,9 @@ use crate::model::orientation::Orientation::{Vertical, Horizontal};
 use std::fmt::{Display, Formatter};
 use core::fmt;
 
-#[derive(Serialize, Deserialize, Debug, PartialOrd, PartialEq, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, PartialOrd, PartialEq, Clone, Copy, Default)]
 pub enum Orientation {
+    #[default]
     Horizontal,
     Vertical,
 }
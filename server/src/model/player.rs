@@ -8,35 +8,47 @@ use crate::model::{
     impact::Impact::{Hit, Miss},
     status::Status::Defeated,
     gameerror::GameError,
-    gameerror::GameError::ShipAlreadyPlaced,
     occupation::Occupation
 };
 use std::fmt::{Display, Formatter};
 use core::fmt;
 use crate::model::size::Size;
 use crate::model::orientation::Orientation;
+use crate::model::adjacency::Adjacency;
+use crate::model::salvosize::SalvoSize;
+use std::collections::BTreeMap;
 
-type Fleet = [Option<Ship>; 5];
+type Fleet = Vec<Ship>;
+
+const PLACEMENT_ATTEMPTS: usize = 1000;
 
 #[derive(Serialize, Deserialize, Debug, PartialOrd, PartialEq, Clone)]
 pub struct Player {
     pub name: String,
     pub secret: String,
-    #[serde(default = "default_fleet")]
+    /// Players without a team play on their own, as a team of one.
+    #[serde(default)]
+    pub team: Option<String>,
+    #[serde(default)]
     fleet: Fleet,
+    #[serde(default)]
+    classes: Vec<Class>,
     #[serde(default = "Status::default")]
     status: Status,
-    #[serde(skip)]
+    #[serde(default)]
     field_size: Size,
-}
-
-fn default_fleet() -> [Option<Ship>; 5] {
-    [None; 5]
+    #[serde(default)]
+    timeouts: u8,
+    #[serde(default)]
+    adjacency: Adjacency,
+    /// Every shot this player has fired, per target player.
+    #[serde(default)]
+    shots: BTreeMap<String, Vec<Point>>,
 }
 
 fn ruler(width: u8, left: &str, inner: &str, border: &str, right: &str) -> String {
     let mut ruler = format!("{}", left);
-    for _ in 0..=width - 1 {
+    for _ in 0..width {
         ruler = ruler + inner + border;
     }
     ruler + inner + right + "\n"
@@ -60,7 +72,7 @@ impl Display for Player {
         let last_y = self.field_size.height - 1;
         let mut output = format!("\n\n     ");
         for x in 0..=last_x {
-            output += &*format!("  {}   ", x);
+            output += &*format!(" {:^4} ", x);
         }
         output += "\n";
         for y in 0..=last_y {
@@ -69,10 +81,10 @@ impl Display for Player {
             } else {
                 inner_ruler(last_x)
             };
-            output += &*format!("  {} ╢", y);
+            output += &*format!("{:>3} ╢", y);
             for x in 0..=last_x {
                 output += &*format!(" {} ", self.probe(&Point::new(x, y)));
-                output += if x == 9 {
+                output += if x == last_x {
                     "║"
                 } else {
                     "│"
@@ -88,137 +100,177 @@ impl Display for Player {
 impl Player {
 
     pub fn new(
-        name: String, secret: String, fleet: Fleet, status: Status, field_size: Size
+        name: String, secret: String, classes: Vec<Class>, field_size: Size, adjacency: Adjacency
     ) -> Player {
         Player {
-            name, secret, fleet, status, field_size
+            name, secret, team: None, fleet: vec![], classes, status: Status::default(), field_size, timeouts: 0, adjacency,
+            shots: BTreeMap::new(),
         }
     }
 
-    pub fn placed(&self, class: &Class) -> bool {
-        for ship in self.fleet.iter() {
-            if ship.is_some() && ship.unwrap().class == *class {
-                return true
-            }
+    pub fn in_team(self, team: Option<String>) -> Player {
+        Player {
+            team, ..self
         }
-        false
+    }
+
+    pub fn team(&self) -> String {
+        self.team.clone().unwrap_or(self.name.clone())
+    }
+
+    pub fn teammate(&self, other: &Player) -> bool {
+        self.name != other.name && self.team() == other.team()
+    }
+
+    pub fn class(&self, name: &str) -> Option<Class> {
+        self.classes.iter().find(|class| class.name == name).cloned()
+    }
+
+    pub fn placed(&self, class: &Class) -> bool {
+        self.fleet.iter().any(|ship| ship.class.same_class(class))
     }
 
     pub fn inside_field(&self, ship: &Ship) -> bool {
-        if ship.coordinates.x >= self.field_size.width {
-            false
-        } else if ship.coordinates.y >= self.field_size.height {
-            false
-        } else {
-            ship.tail_end() <= match ship.orientation {
-                Orientation::Horizontal => self.field_size.width,
-                Orientation::Vertical => self.field_size.height,
-            } - 1
-        }
+        ship.inside(&self.field_size)
     }
 
     pub fn overlap(&self, ship: &Ship) -> Option<Ship> {
-        for other in &self.fleet {
-            if other.is_some() && ship.overlap(other.as_ref().unwrap()) {
-                return other.clone()
-            }
-        }
-        None
+        self.fleet.iter().find(|other| ship.overlap(other)).cloned()
+    }
+
+    pub fn touching(&self, ship: &Ship) -> Option<Ship> {
+        self.fleet.iter().find(|other| ship.touches(other, self.adjacency)).cloned()
     }
 
     pub fn check_placement(&self, ship: &Ship) -> Result<(), GameError> {
-        if self.placed(&ship.class) {
-            Err(GameError::ShipAlreadyPlaced(ship.class))
-        } else if !self.inside_field(&ship) {
+        if self.class(&ship.class.name).is_none() {
+            Err(GameError::UnknownClass(ship.class.name()))
+        } else if self.placed(&ship.class) {
+            Err(GameError::ShipAlreadyPlaced(ship.class.clone()))
+        } else if ship.rotation >= 4 {
+            Err(GameError::InvalidRotation(ship.rotation))
+        } else if !self.inside_field(ship) {
             Err(GameError::ShipOutOfBounds(ship.coordinates, ship.orientation, ship.class.size()))
-        } else if let Some(other) = self.overlap(&ship) {
+        } else if let Some(other) = self.overlap(ship) {
             Err(GameError::ShipOverlaps(other))
+        } else if let Some(other) = self.touching(ship) {
+            Err(GameError::ShipTouches(other))
         } else {
             Ok(())
         }
     }
 
-    pub fn find_empty_spot(&self) -> Option<usize> {
-        for (index, option) in self.fleet.iter().enumerate() {
-            if option.is_none() {
-                return Some(index);
-            }
-        }
-        None
-    }
-
     pub fn place_ship(&self, ship: Ship) -> Result<Player, GameError> {
         self.check_placement(&ship)?;
         let mut player = self.clone();
-        match self.find_empty_spot() {
-            None => return Err(ShipAlreadyPlaced(ship.class)),
-            Some(empty_spot) => player.fleet[empty_spot] = Some(ship),
-        }
+        player.fleet.push(ship);
         Ok(player)
     }
 
+    pub fn complete_fleet(&self, dice: &mut impl FnMut(u8) -> u8) -> Player {
+        let mut player = self.clone();
+        for class in self.classes.iter().filter(|class| !self.placed(class)) {
+            for _ in 0..PLACEMENT_ATTEMPTS {
+                let coordinates = Point::new(dice(self.field_size.width), dice(self.field_size.height));
+                let ship = Ship::new(coordinates, Orientation::Horizontal, class.clone())
+                    .rotated(dice(4), dice(2) == 1);
+                if let Ok(placed) = player.place_ship(ship) {
+                    player = placed;
+                    break;
+                }
+            }
+        }
+        player
+    }
+
+    pub fn ships(&self) -> Vec<Ship> {
+        self.fleet.clone()
+    }
+
     pub fn fleet_size(&self) -> usize {
-        self.fleet.iter().filter(|option| option.is_some()).count()
+        self.fleet.len()
     }
 
     pub fn is_fleet_complete(&self) -> bool {
-        self.fleet_size() == 5
+        self.classes.iter().all(|class| self.placed(class))
     }
 
     pub fn probe(&self, coordinates: &Point) -> Occupation {
-        for option in self.fleet {
-            if let Some(ship) = option {
-                if ship.is_hit(coordinates) {
-                    return ship.probe(coordinates);
-                }
+        for ship in &self.fleet {
+            if ship.is_hit(coordinates) {
+                return ship.probe(coordinates);
             }
         }
         Occupation::Empty
     }
 
     pub fn active_ships(&self) -> usize {
-        active_ships(self.fleet)
+        active_ships(&self.fleet)
+    }
+
+    pub fn allowance(&self, salvo_size: SalvoSize) -> u8 {
+        let afloat = self.fleet.iter().filter(|ship| !ship.is_sunk());
+        match salvo_size {
+            SalvoSize::Ships => self.active_ships() as u8,
+            SalvoSize::Fixed(shots) => shots,
+            SalvoSize::Cells => afloat.map(|ship| ship.class.remaining()).fold(0, u8::saturating_add),
+            SalvoSize::Largest => afloat.map(|ship| ship.class.size()).max().unwrap_or(0),
+        }
     }
 
     pub fn is_defeated(&self) -> bool {
-        is_defeated(self.fleet)
+        self.status == Defeated || is_defeated(&self.fleet)
+    }
+
+    pub fn timeouts(&self) -> u8 {
+        self.timeouts
+    }
+
+    pub fn timed_out(&self) -> Player {
+        let mut player = self.clone();
+        player.timeouts += 1;
+        player
+    }
+
+    pub fn forfeit(&self) -> Player {
+        let mut player = self.clone();
+        player.status = Defeated;
+        player
+    }
+
+    pub fn shots(&self) -> BTreeMap<String, Vec<Point>> {
+        self.shots.clone()
+    }
+
+    pub fn has_fired_at(&self, target: &String, coordinates: &Point) -> bool {
+        self.shots.get(target).map_or(false, |shots| shots.contains(coordinates))
+    }
+
+    pub fn fired_at(&mut self, target: &str, coordinates: Point) {
+        self.shots.entry(target.to_string()).or_default().push(coordinates)
     }
 
     pub fn shoot(&self, coordinates: &Point) -> Impact<(Player, Ship)> {
         for (index, ship) in self.fleet.iter().enumerate() {
-            if ship.is_some() {
-                match ship.as_ref().unwrap().shoot(coordinates) {
-                    Hit(hit_ship) => {
-                        let mut new_fleet = self.fleet;
-                        new_fleet[index] = Some(hit_ship);
-                        return Hit((
-                            Player::new(
-                                self.name.clone(), self.secret.clone(), new_fleet,
-                                if is_defeated(new_fleet) {
-                                    Defeated
-                                } else {
-                                    self.status
-                                },
-                                self.field_size
-                            ), hit_ship
-                        ))
-                    },
-                    Miss => {}
+            if let Hit(hit_ship) = ship.shoot(coordinates) {
+                let mut player = self.clone();
+                player.fleet[index] = hit_ship.clone();
+                if is_defeated(&player.fleet) {
+                    player.status = Defeated;
                 }
+                return Hit((player, hit_ship))
             }
         }
         Miss
     }
 }
 
-pub fn active_ships(fleet: Fleet) -> usize {
+pub fn active_ships(fleet: &[Ship]) -> usize {
     fleet.iter()
-        .filter(|option| option.is_some())
-        .map(|option| option.unwrap())
         .filter(|ship| !ship.is_sunk())
         .count()
 }
 
-pub fn is_defeated(fleet: Fleet) -> bool {
+pub fn is_defeated(fleet: &[Ship]) -> bool {
     active_ships(fleet) == 0
 }
@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+use crate::model::adjacency::Adjacency;
+use crate::model::config::Config;
+use crate::model::disclosure::Disclosure;
+use crate::model::salvosize::SalvoSize;
+use crate::model::friendlyfire::FriendlyFire;
+use crate::model::targetpolicy::TargetPolicy;
+
+#[derive(Serialize, Deserialize, Debug, PartialOrd, PartialEq, Clone)]
+pub struct Rules {
+    pub adjacency: Adjacency,
+    pub disclosure: Disclosure,
+    pub salvo_size: SalvoSize,
+    pub friendly_fire: FriendlyFire,
+    pub reject_repeats: bool,
+    pub self_targeting: TargetPolicy,
+    pub defeated_targets: TargetPolicy,
+}
+
+impl From<&Config> for Rules {
+    fn from(config: &Config) -> Self {
+        Rules {
+            adjacency: config.adjacency,
+            disclosure: config.disclosure,
+            salvo_size: config.salvo_size,
+            friendly_fire: config.friendly_fire,
+            reject_repeats: config.reject_repeats,
+            self_targeting: config.self_targeting,
+            defeated_targets: config.defeated_targets,
+        }
+    }
+}
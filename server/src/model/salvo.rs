@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+use crate::model::point::Point;
+use crate::model::shotresult::ShotResult;
+
+#[derive(Serialize, Deserialize, Debug, PartialOrd, PartialEq, Clone)]
+pub struct SalvoShot {
+    pub target: String,
+    pub x: u8,
+    pub y: u8,
+}
+
+impl SalvoShot {
+    pub fn coordinates(&self) -> Point {
+        Point::new(self.x, self.y)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialOrd, PartialEq, Clone)]
+pub struct SalvoResult {
+    pub shots: u8,
+    pub hits: u8,
+    /// The result of every shot, only disclosed in classic mode.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub results: Vec<ShotResult>,
+}
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+#[derive(Serialize, Deserialize, Debug, PartialOrd, PartialEq, Clone, Copy, Default)]
+pub enum SalvoSize {
+    #[default]
+    Ships,
+    Fixed(u8),
+    Cells,
+    Largest,
+}
+
+impl FromStr for SalvoSize {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "ships" => Ok(SalvoSize::Ships),
+            "cells" => Ok(SalvoSize::Cells),
+            "largest" => Ok(SalvoSize::Largest),
+            other => match other.parse() {
+                Ok(shots) if shots > 0 => Ok(SalvoSize::Fixed(shots)),
+                _ => Err(format!("Unknown salvo size: {}", other)),
+            },
+        }
+    }
+}
@@ -6,14 +6,24 @@ use crate::model::range::Range;
 use crate::model::orientation::Orientation::{Horizontal, Vertical};
 use crate::model::impact::Impact;
 use crate::model::impact::Impact::{Hit, Miss};
-use crate::messaging::translate::deserialize;
+use crate::messaging::translate::deserialize_with;
 use crate::messaging::translate;
 use crate::model::occupation::Occupation;
+use crate::model::size::Size;
+use crate::model::adjacency::Adjacency;
+use crate::model::encoding::Encoding;
 
-#[derive(Serialize, Deserialize, Debug, PartialOrd, PartialEq, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, PartialOrd, PartialEq, Clone)]
 pub struct Ship {
     pub(crate) coordinates: Point,
+    #[serde(default)]
     pub(crate) orientation: Orientation,
+    /// Number of clockwise quarter turns, on top of the orientation.
+    #[serde(default)]
+    pub(crate) rotation: u8,
+    /// Mirrored along the vertical axis, before rotating.
+    #[serde(default)]
+    pub(crate) mirrored: bool,
     #[serde(default = "Class::default")]
     pub class: Class,
 }
@@ -23,24 +33,32 @@ impl Ship {
         Ship {
             coordinates: coordinates,
             orientation: orientation,
+            rotation: 0,
+            mirrored: false,
             class: class
         }
     }
 
-    pub fn from_json(json: impl Into<String>, class: Class) -> translate::Result<Self> {
-        let mut ship: Ship = deserialize(&json.into())?;
+    pub fn rotated(self, rotation: u8, mirrored: bool) -> Self {
+        Ship {
+            rotation, mirrored, ..self
+        }
+    }
+
+    pub fn from_payload(payload: impl Into<String>, class: Class, encoding: Encoding) -> translate::Result<Self> {
+        let mut ship: Ship = deserialize_with(&payload.into(), encoding)?;
         ship.class = class;
         Ok(ship)
     }
 
-    pub fn transposed_to(self, orientation: Orientation) -> Ship {
+    pub fn transposed_to(&self, orientation: Orientation) -> Ship {
         if orientation == self.orientation {
-            self
+            self.clone()
         } else {
             Ship::new(
                 self.coordinates.transposed(),
                 self.orientation.transposed(),
-                self.class,
+                self.class.clone(),
             )
         }
     }
@@ -60,55 +78,74 @@ impl Ship {
         Range::new(min, min + self.class.size())
     }
 
+    /// Offsets of the cells relative to the coordinates, in the same order as the cells of the class.
+    fn offsets(&self) -> Vec<(i16, i16)> {
+        let turns = (self.rotation + match self.orientation {
+            Horizontal => 0,
+            Vertical => 1,
+        }) % 4;
+        let cells: Vec<(i16, i16)> = self.class.shape().iter()
+            .map(|cell| {
+                let (mut x, mut y) = (cell.x as i16, cell.y as i16);
+                if self.mirrored {
+                    x = -x;
+                }
+                for _ in 0..turns {
+                    let turned = (-y, x);
+                    x = turned.0;
+                    y = turned.1;
+                }
+                (x, y)
+            })
+            .collect();
+        let min_x = cells.iter().map(|(x, _)| *x).min().unwrap_or(0);
+        let min_y = cells.iter().map(|(_, y)| *y).min().unwrap_or(0);
+        cells.into_iter().map(|(x, y)| (x - min_x, y - min_y)).collect()
+    }
+
+    pub fn cells(&self) -> Vec<(u16, u16)> {
+        self.offsets().into_iter()
+            .map(|(x, y)| (self.coordinates.x as u16 + x as u16, self.coordinates.y as u16 + y as u16))
+            .collect()
+    }
+
+    pub fn inside(&self, size: &Size) -> bool {
+        self.cells().iter().all(|(x, y)| *x < size.width as u16 && *y < size.height as u16)
+    }
+
     pub fn overlap(&self, other: &Ship) -> bool {
-        if self.coordinates == other.coordinates {
-            true
-        } else {
-            // Transpose ships to fixed orientations to reduce number of flows for this test
-            let self_transposed = self.transposed_to(Horizontal);
-
-            if self.orientation == other.orientation {
-                let other_transposed = other.transposed_to(Horizontal);
-                self_transposed.coordinates.y == other_transposed.coordinates.y
-                    && self_transposed.range().overlap(other_transposed.range())
-            } else {
-                let other_transposed = other.transposed_to(Vertical);
-                self_transposed.range().within(other_transposed.coordinates.x)
-                    && other_transposed.range().within(self_transposed.coordinates.y)
-            }
-        }
+        let cells = other.cells();
+        self.cells().iter().any(|cell| cells.contains(cell))
+    }
+
+    pub fn touches(&self, other: &Ship, adjacency: Adjacency) -> bool {
+        let cells = other.cells();
+        self.cells().iter().any(|cell| cells.iter().any(|other| adjacency.neighbours(*cell, *other)))
     }
 
     pub fn is_hit(&self, coordinates: &Point) -> bool {
-        match self.orientation {
-            Horizontal => self.range().within(coordinates.x) && self.coordinates.y == coordinates.y,
-            Vertical => self.range().within(coordinates.y) && self.coordinates.x == coordinates.x,
-        }
+        self.global_to_local(coordinates).is_some()
     }
 
     pub fn probe(&self, coordinates: &Point) -> Occupation {
-        if self.is_hit(coordinates) {
-            self.class.probe(self.global_to_local(coordinates) as usize)
-        } else {
-            Occupation::Empty
+        match self.global_to_local(coordinates) {
+            Some(cell) => self.class.probe(cell as usize),
+            None => Occupation::Empty,
         }
     }
 
-    pub fn global_to_local(&self, coordinates: &Point) -> u8 {
-        match self.orientation {
-            Horizontal => coordinates.x - self.coordinates.x,
-            Vertical => coordinates.y - self.coordinates.y,
-        }
+    pub fn global_to_local(&self, coordinates: &Point) -> Option<u8> {
+        let coordinates = (coordinates.x as u16, coordinates.y as u16);
+        self.cells().iter().position(|cell| *cell == coordinates).map(|cell| cell as u8)
     }
 
     pub fn shoot(&self, coordinates: &Point) -> Impact<Ship> {
-        if self.is_hit(coordinates) {
-            Hit(Ship::new(
-                self.coordinates, self.orientation,
-                self.class.shoot(self.global_to_local(coordinates))
-            ))
-        } else {
-            Miss
+        match self.global_to_local(coordinates) {
+            Some(cell) => Hit(Ship {
+                class: self.class.shoot(cell),
+                ..self.clone()
+            }),
+            None => Miss,
         }
     }
 
@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+use crate::model::point::Point;
+
+#[derive(Serialize, Deserialize, Debug, PartialOrd, PartialEq, Clone)]
+pub struct ShotResult {
+    pub target: String,
+    pub coordinates: Point,
+    pub hit: bool,
+}
+
+impl ShotResult {
+    pub fn new(target: String, coordinates: Point, hit: bool) -> Self {
+        ShotResult {
+            target, coordinates, hit
+        }
+    }
+}
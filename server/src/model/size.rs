@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
 use core::fmt;
+use std::str::FromStr;
+use crate::model::point::Point;
 
 #[derive(Serialize, Deserialize, Debug, PartialOrd, PartialEq, Clone, Copy)]
 pub struct Size {
@@ -14,6 +16,22 @@ impl Display for Size {
     }
 }
 
+impl FromStr for Size {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid size {}, expected <width>x<height>", input);
+        let (width, height) = input.split_once('x').ok_or_else(invalid)?;
+        let width: u8 = width.parse().map_err(|_| invalid())?;
+        let height: u8 = height.parse().map_err(|_| invalid())?;
+        if width == 0 || height == 0 {
+            Err(invalid())
+        } else {
+            Ok(Size::new(width, height))
+        }
+    }
+}
+
 impl Default for Size {
     fn default() -> Self {
         Self::new(10, 10)
@@ -30,4 +48,8 @@ impl Size {
     pub(crate) fn transposed(self) -> Self {
         Size::new(self.height, self.width)
     }
+
+    pub fn contains(&self, point: &Point) -> bool {
+        point.x < self.width && point.y < self.height
+    }
 }
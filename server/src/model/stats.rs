@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, Debug, PartialOrd, PartialEq, Clone, Default)]
+pub struct PlayerStats {
+    pub shots: u32,
+    pub hits: u32,
+    pub accuracy: f32,
+    pub ships_sunk: u32,
+    pub turns_survived: u32,
+    pub elimination: Option<usize>,
+    pub turn_times: Vec<u64>,
+    pub repeated_shots: u32,
+}
+
+impl PlayerStats {
+    fn shot(&mut self, hit: bool, repeated: bool) {
+        self.shots += 1;
+        self.repeated_shots += repeated as u32;
+        self.hits += hit as u32;
+        self.accuracy = self.hits as f32 / self.shots as f32;
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialOrd, PartialEq, Clone)]
+pub struct Summary {
+    pub winner: Option<String>,
+    pub turns: u32,
+    pub shots: u32,
+    pub hits: u32,
+    pub accuracy: f32,
+    pub eliminations: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialOrd, PartialEq, Clone, Default)]
+pub struct Stats {
+    pub players: BTreeMap<String, PlayerStats>,
+    pub eliminations: Vec<String>,
+    pub turns: u32,
+    turn_started: Option<(String, u64)>,
+}
+
+impl Stats {
+    fn player(&mut self, name: &str) -> &mut PlayerStats {
+        self.players.entry(name.to_string()).or_default()
+    }
+
+    pub fn shot(&mut self, shooter: &str, hit: bool, repeated: bool) {
+        self.player(shooter).shot(hit, repeated)
+    }
+
+    pub fn sunk(&mut self, shooter: &str) {
+        self.player(shooter).ships_sunk += 1;
+    }
+
+    pub fn eliminated(&mut self, name: &String) {
+        if !self.eliminations.contains(name) {
+            self.eliminations.push(name.clone());
+            self.player(name).elimination = Some(self.eliminations.len());
+        }
+    }
+
+    pub fn turn_started(&mut self, name: &str, active_players: &[String], millis: u64) {
+        self.turns += 1;
+        for player in active_players {
+            self.player(player).turns_survived += 1;
+        }
+        self.turn_started = Some((name.to_string(), millis));
+    }
+
+    pub fn turn_ended(&mut self, millis: u64) {
+        if let Some((name, started)) = self.turn_started.take() {
+            self.player(&name).turn_times.push(millis.saturating_sub(started));
+        }
+    }
+
+    pub fn summary(&self, winner: Option<String>) -> Summary {
+        let shots = self.players.values().map(|player| player.shots).sum();
+        let hits = self.players.values().map(|player| player.hits).sum();
+        Summary {
+            winner,
+            turns: self.turns,
+            shots,
+            hits,
+            accuracy: if shots == 0 {
+                0.0
+            } else {
+                hits as f32 / shots as f32
+            },
+            eliminations: self.eliminations.clone(),
+        }
+    }
+}
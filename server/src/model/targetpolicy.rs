@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+#[derive(Serialize, Deserialize, Debug, PartialOrd, PartialEq, Clone, Copy, Default)]
+pub enum TargetPolicy {
+    #[default]
+    Allow,
+    Reject,
+    RejectAndConsume,
+}
+
+impl FromStr for TargetPolicy {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "allow" => Ok(TargetPolicy::Allow),
+            "reject" => Ok(TargetPolicy::Reject),
+            "reject-and-consume" => Ok(TargetPolicy::RejectAndConsume),
+            other => Err(format!("Unknown target policy: {}", other)),
+        }
+    }
+}
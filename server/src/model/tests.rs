@@ -2,37 +2,40 @@
 #[cfg(test)]
 mod tests {
     use crate::model::{
-        ship::Ship, point::Point, range::Range, player::Player, game::Game
+        ship::Ship, point::Point, range::Range, player::Player, class::Class, size::Size,
+        adjacency::Adjacency, impact::Impact, game::Game, config::Config, salvosize::SalvoSize,
+        gameerror::GameError, gamestate::GameState,
     };
-    use crate::model::class::Class::{Carrier, Battleship, Destroyer, Submarine, PatrolBoat};
     use lazy_static::lazy_static;
     use std::ops::Deref;
     use crate::model::orientation::Orientation::{Horizontal, Vertical};
-    use crate::model::status::Status::Requested;
 
     lazy_static! {
         static ref CARRIER: Ship = Ship::new(
-            Point::new(1, 1), Vertical, Carrier([false; 5])
+            Point::new(1, 1), Vertical, Class::new("carrier", 5, "C")
         );
         static ref BATTLESHIP: Ship = Ship::new(
-            Point::new(2, 2), Horizontal, Battleship([false; 4])
+            Point::new(2, 2), Horizontal, Class::new("battleship", 4, "B")
         );
         static ref DESTROYER: Ship = Ship::new(
-            Point::new(2, 2), Vertical, Destroyer([false; 3])
+            Point::new(2, 2), Vertical, Class::new("destroyer", 3, "D")
         );
         static ref SUBMARINE: Ship = Ship::new(
-            Point::new(3, 1), Vertical, Submarine([false; 3])
+            Point::new(3, 1), Vertical, Class::new("submarine", 3, "S")
         );
         static ref PATROL_BOAT: Ship = Ship::new(
-            Point::new(5,2), Horizontal, PatrolBoat([false; 2])
+            Point::new(5,2), Horizontal, Class::new("patrolboat", 2, "P")
         );
         static ref PLAYER: Player = Player::new(
             "Henkie".to_string(),
             "Cockadoodledoo".to_string(),
-            [Some(*BATTLESHIP), None, None, None, None],
-            Requested
+            Class::standard_fleet(),
+            Size::default(),
+            Adjacency::None,
+        ).place_ship(BATTLESHIP.clone()).unwrap();
+        static ref ELL: Class = Class::shaped(
+            "ell", "L", vec![Point::new(0, 0), Point::new(0, 1), Point::new(0, 2), Point::new(1, 2)]
         );
-        static ref GAME: Game = Game::from(vec![PLAYER.clone()]);
     }
 
     #[test]
@@ -43,14 +46,7 @@ mod tests {
         );
         assert_eq!(
             PATROL_BOAT.transposed_to(Vertical),
-            Ship {
-                coordinates: Point {
-                    x: 2,
-                    y: 5,
-                },
-                orientation: Vertical,
-                class: PatrolBoat([false; 2])
-            }
+            Ship::new(Point::new(2, 5), Vertical, Class::new("patrolboat", 2, "P"))
         );
     }
 
@@ -76,80 +72,170 @@ mod tests {
     #[test]
     fn test_overlapping_ships() {
         let ship_pairs = [
-            (*CARRIER, *BATTLESHIP, false, "Non overlapping"),
-            (*BATTLESHIP, *DESTROYER, true, "Identical coordinates with different orientations"),
-            (*BATTLESHIP, *BATTLESHIP, true, "Identical coordinates with same orientation"),
-            (*BATTLESHIP, *SUBMARINE, true, "Overlapping with different orientations"),
-            (*BATTLESHIP, *PATROL_BOAT, true, "Overlapping with same orientation"),
+            (&*CARRIER, &*BATTLESHIP, false, "Non overlapping"),
+            (&*BATTLESHIP, &*DESTROYER, true, "Identical coordinates with different orientations"),
+            (&*BATTLESHIP, &*BATTLESHIP, true, "Identical coordinates with same orientation"),
+            (&*BATTLESHIP, &*SUBMARINE, true, "Overlapping with different orientations"),
+            (&*BATTLESHIP, &*PATROL_BOAT, true, "Overlapping with same orientation"),
         ];
 
         for (one, other, expected, message) in ship_pairs.iter() {
             let one_result = one.overlap(other);
             let other_result = other.overlap(one);
-        //
-        //     info!(
-        //         "{}:\n  One: {:?}, result: {}\n  Another: {:?}, result: {}",
-        //         message, one, one_result, other, other_result
-        //     );
-        //
-        //     assert_eq!(one_result, *expected);
-        //     assert_eq!(one_result, other_result);
+
+            assert_eq!(one_result, *expected, "{}", message);
+            assert_eq!(one_result, other_result, "{}", message);
         }
     }
 
+    #[test]
+    fn test_rotated_ship_cells() {
+        let ship = Ship::new(Point::new(1, 1), Horizontal, ELL.clone());
+        assert_eq!(ship.cells(), vec![(1, 1), (1, 2), (1, 3), (2, 3)]);
+        assert_eq!(ship.clone().rotated(1, false).cells(), vec![(3, 1), (2, 1), (1, 1), (1, 2)]);
+        assert_eq!(ship.clone().rotated(0, true).cells(), vec![(2, 1), (2, 2), (2, 3), (1, 3)]);
+        assert_eq!(
+            Ship::new(Point::new(1, 1), Vertical, ELL.clone()).cells(),
+            ship.rotated(1, false).cells()
+        );
+    }
+
+    #[test]
+    fn test_placement_invalid_rotation() {
+        assert_eq!(
+            PLAYER.check_placement(&CARRIER.clone().rotated(255, false)),
+            Err(GameError::InvalidRotation(255))
+        )
+    }
+
     #[test]
     fn test_placement_existing_class() {
-        assert!(!PLAYER.check_placement(
-            &Ship {
-                coordinates: Point {
-                    x: 4,
-                    y: 7,
-                },
-                orientation: Horizontal,
-                class: Battleship([false; 4]),
-            }
-        ))
+        assert!(PLAYER.check_placement(
+            &Ship::new(Point::new(4, 7), Horizontal, Class::new("battleship", 4, "B"))
+        ).is_err())
     }
 
     #[test]
     fn test_placement_outside_field() {
-        assert!(!PLAYER.check_placement(
-            &Ship {
-                coordinates: Point {
-                    x: 4,
-                    y: 7,
-                },
-                orientation: Vertical,
-                class: Carrier([false; 5]),
-            }
-        ))
+        assert!(PLAYER.check_placement(
+            &Ship::new(Point::new(4, 7), Vertical, Class::new("carrier", 5, "C"))
+        ).is_err())
     }
 
     #[test]
     fn test_overlapping_placement() {
-        assert!(!PLAYER.check_placement(DESTROYER.deref()))
+        assert!(PLAYER.check_placement(DESTROYER.deref()).is_err())
     }
 
     #[test]
     fn test_correct_placement() {
-        assert!(PLAYER.check_placement(CARRIER.deref()))
+        assert!(PLAYER.check_placement(CARRIER.deref()).is_ok())
+    }
+
+    #[test]
+    fn test_touching_placement() {
+        let touching = Ship::new(Point::new(6, 3), Horizontal, Class::new("patrolboat", 2, "P"));
+        let player = Player::new(
+            "Henkie".to_string(), "Cockadoodledoo".to_string(), Class::standard_fleet(), Size::default(),
+            Adjacency::Orthogonal,
+        ).place_ship(BATTLESHIP.clone()).unwrap();
+        assert!(player.check_placement(&touching).is_ok());
+
+        let player = Player::new(
+            "Henkie".to_string(), "Cockadoodledoo".to_string(), Class::standard_fleet(), Size::default(),
+            Adjacency::Diagonal,
+        ).place_ship(BATTLESHIP.clone()).unwrap();
+        assert!(player.check_placement(&touching).is_err());
     }
 
     #[test]
     fn test_shoot_and_hit() {
-        let mut game = GAME.deref().clone();
-        assert!(match game.fire("Henkie", &Point { x: 4, y: 2 }) {
-            Ok(result) => result,
-            Err(error) => false,
+        assert!(match PLAYER.shoot(&Point::new(4, 2)) {
+            Impact::Hit(_) => true,
+            Impact::Miss => false,
         });
     }
 
     #[test]
     fn test_shoot_and_miss() {
-        let mut game = GAME.deref().clone();
-        assert!(!match game.fire(String::from("Henkie"), &Point { x: 4, y: 3 }) {
-            Ok(result) => result,
-            Err(error) => false,
+        assert!(!match PLAYER.shoot(&Point::new(4, 3)) {
+            Impact::Hit(_) => true,
+            Impact::Miss => false,
         });
     }
+
+    #[test]
+    fn test_allowance() {
+        let player = PLAYER.place_ship(CARRIER.clone()).unwrap();
+        let player = match player.shoot(&Point::new(2, 2)) {
+            Impact::Hit((player, _)) => player,
+            Impact::Miss => panic!("Expected a hit"),
+        };
+        assert_eq!(player.allowance(SalvoSize::Ships), 2);
+        assert_eq!(player.allowance(SalvoSize::Fixed(3)), 3);
+        assert_eq!(player.allowance(SalvoSize::Cells), 8);
+        assert_eq!(player.allowance(SalvoSize::Largest), 5);
+    }
+
+    #[test]
+    fn test_turn_order() {
+        let mut game = Game::new(Size::default(), "test".to_string(), Config::default());
+        for name in &["Henkie", "Bassie", "Adriaan"] {
+            let mut player = PLAYER.clone();
+            player.name = name.to_string();
+            game.update_player(player);
+        }
+        assert!(game.start(|_| 0));
+
+        let (_, player) = game.find_player("Henkie").unwrap();
+        game.update_player(player.timed_out());
+        let (_, player) = game.find_player("Bassie").unwrap();
+        game.update_player(player.forfeit());
+        assert_eq!(game.player_list().to_vec(), vec!["Henkie", "Bassie", "Adriaan"]);
+        assert_eq!(game.active_player_count(), 2);
+
+        game.next_turn();
+        assert_eq!(game.state, GameState::Underway((2, "Adriaan".to_string()), 0, 0));
+        game.next_turn();
+        assert_eq!(game.state, GameState::Underway((0, "Henkie".to_string()), 0, 0));
+    }
+
+    #[test]
+    fn test_reset_keeps_turn() {
+        let mut game = Game::new(Size::default(), "test".to_string(), Config::default());
+        game.update_player(PLAYER.clone());
+        assert!(game.start(|_| 0));
+        game.next_turn();
+        game.next_turn();
+        game.reset();
+        assert_eq!(game.state, GameState::Lobby);
+        assert!(game.players.is_empty());
+        assert_eq!(game.turn, 2);
+    }
+
+    #[test]
+    fn test_teams() {
+        let mut game = Game::new(Size::default(), "test".to_string(), Config::default());
+        let player = PLAYER.clone().in_team(Some("red".to_string()));
+        game.update_player(player.clone());
+        let mut teammate = PLAYER.clone().in_team(Some("red".to_string()));
+        teammate.name = "Bassie".to_string();
+        game.update_player(teammate.clone());
+        let mut opponent = PLAYER.clone();
+        opponent.name = "Adriaan".to_string();
+        game.update_player(opponent.clone());
+
+        assert!(player.teammate(&teammate));
+        assert!(!player.teammate(&player));
+        assert!(!player.teammate(&opponent));
+        assert_eq!(game.active_teams(), vec!["red".to_string(), "Adriaan".to_string()]);
+    }
+
+    #[test]
+    fn test_fleet_fits_board() {
+        let args = |size: &str| vec!["--size".to_string(), size.to_string()];
+        assert!(Config::from_args(&args("5x1")).is_ok());
+        assert!(Config::from_args(&args("1x5")).is_ok());
+        assert!(Config::from_args(&args("4x4")).is_err());
+    }
 }
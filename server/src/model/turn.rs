@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, PartialOrd, PartialEq, Clone)]
+pub struct Turn {
+    pub player: String,
+    pub deadline: u64,
+    pub shots: u8,
+}
+
+impl Turn {
+    pub fn new(player: String, deadline: u64, shots: u8) -> Self {
+        Turn {
+            player, deadline, shots
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, PartialOrd, PartialEq, Clone)]
+pub struct Winner {
+    pub team: String,
+    pub members: Vec<String>,
+}
+
+impl Winner {
+    pub fn new(team: String, members: Vec<String>) -> Self {
+        Winner {
+            team, members
+        }
+    }
+}